@@ -1,76 +1,716 @@
-use clap::Args;
-use geocalc::{BHOrientation, BHOrientationLine, Borehole as GCBorehole, RawMeasurement};
-use std::fs::File;
+use clap::{Args, ValueEnum};
+use geocalc::{
+    AzimuthReference, BHOrientation, BHOrientationLine, Borehole as GCBorehole, Collar, CoreRun,
+    InclinationConvention as GCInclinationConvention, IntegrityIssue, MagneticModel, Project,
+    UtmZone,
+};
+use std::{collections::BTreeMap, fs, io::Write};
 
+use super::{
+    config::{Config, Conventions},
+    conventions::{AzimuthDatum, BetaConvention, InclinationConvention},
+    exit_with_error,
+    input::{is_las, read_las, CsvInput},
+    local_grid::LocalGrid,
+    output::{open_output, Format, MeasurementWriter},
+    survey_merge::{print_disagreements, write_disagreements},
+    survey_qa::{join_flags, write_report, SurveyQa},
+    survey_smoothing::{self, SurveySmoothing},
+    uncertainty::{Method, Uncertainty},
+};
+
+/// Unset options are taken from the configuration file, see the `init` command
 #[derive(Args)]
 pub struct Borehole {
-    /// Path to csv file containing borehole orientation data
+    /// Path to the configuration file [default: geocalc.toml when it exists]
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Path to csv file containing borehole orientation data, `-` reads from stdin.
+    /// Input tables can also be read from a worksheet of a workbook, e.g. logs.xlsx#Survey
+    /// Expected format:
+    /// [hole_id,][survey_type,]depth,bearing,inclination
+    /// LAS 2.0 deviation logs with DEPT, AZIM and INCL curves are read from .las files, the WELL
+    /// of the file is the hole id of its stations. LAS inclinations are taken from vertical unless
+    /// an inclination convention is set for the hole.
+    /// Can be given several times for surveys of different types, e.g. gyro=gyro.csv, which are merged
+    /// by --survey-priority
+    #[arg(long)]
+    pub dh_orientation: Vec<String>,
+
+    /// Survey types from the most to the least trusted, e.g. gyro,magnetic,planned.
+    /// Each survey is used over its depth range where no more trusted survey covers the hole
+    /// [default: gyro,multishot,magnetic,planned]
+    #[arg(long, value_delimiter = ',')]
+    pub survey_priority: Option<Vec<String>>,
+
+    /// Path to where the disagreement between merged surveys is written as csv
+    #[arg(long)]
+    pub survey_merge_report: Option<String>,
+
+    /// Path to csv file containing borehole measurements, `-` reads from stdin
+    /// Expected format:
+    /// [hole_id,]depth,alpha,beta[,orientation_line]
+    /// beta may be left empty for unoriented core.
+    /// Any other columns, e.g. structure type or comments, are copied to the output.
+    #[arg(long)]
+    pub dh_measurements: Option<String>,
+
+    /// The measurements file can also be given without the --dh-measurements flag
+    #[arg(conflicts_with = "dh_measurements", hide = true)]
+    pub measurements: Option<String>,
+
+    /// Path to csv file containing the drill runs and the confidence of their orientation marks
     /// Expected format:
-    /// depth,bearing,inclination
+    /// [hole_id,]from,to,confidence[,orientation_line]
+    /// where confidence is one of good, fair, poor or none
     #[arg(long)]
-    pub dh_orientation: String,
+    pub dh_runs: Option<String>,
 
-    /// Path to csv file containing borehole measurements
+    /// Path to csv file containing the collars of the holes
     /// Expected format:
-    /// depth,alpha,beta
+    /// hole_id,x,y,z,eoh[,date][,azimuth_datum][,declination][,latitude,longitude]
+    /// where azimuth_datum is one of true, magnetic or grid.
+    /// Grid bearings need the UTM zone of the collar coordinates, see --utm-zone
+    #[arg(long)]
+    pub dh_collars: Option<String>,
+
+    /// Interval table such as lithology or alteration, given as NAME=PATH, e.g. lithology=lith.csv.
+    /// Expected format:
+    /// [hole_id,]from,to[,...]
+    /// The intervals are checked against the collars and surveys. Can be given several times.
+    #[arg(long, value_name = "NAME=PATH")]
+    pub dh_intervals: Vec<String>,
+
+    /// Path to a geomagnetic model coefficient file (.COF) in the WMM layout, replaces the bundled WMM2025.
+    /// Used for the declination of holes with magnetic bearings from the collar location and date.
+    /// WMM2025 covers 2025.0 to 2030.0, older holes need an earlier model (e.g. IGRF) or a declination.
+    #[arg(long)]
+    pub magnetic_model: Option<String>,
+
+    /// The north reference of the survey bearings, takes precedence over the collars [default: true]
+    #[arg(long, value_enum)]
+    pub azimuth_datum: Option<AzimuthDatum>,
+
+    /// The north reference of the bearings of a survey type, given as TYPE=DATUM, e.g. gyro=true.
+    /// Takes precedence over --azimuth-datum and the collars, each survey is referred to true north
+    /// before the surveys are merged. Can be given several times.
+    #[arg(long, value_name = "TYPE=DATUM", value_parser = parse_survey_datum)]
+    pub survey_datum: Vec<(String, AzimuthDatum)>,
+
+    /// The magnetic declination in degrees (positive east) for magnetic bearings
+    #[arg(long, allow_hyphen_values = true)]
+    pub declination: Option<f64>,
+
+    /// The UTM zone of the collar coordinates, e.g. 55S.
+    /// Used for the grid convergence of grid bearings and grid north output.
+    #[arg(long)]
+    pub utm_zone: Option<UtmZone>,
+
+    #[command(flatten)]
+    pub local_grid: LocalGrid,
+
+    /// The hole id used for rows without a hole_id column, defaults to the WELL of the first .las survey
+    /// [default: dh123]
+    #[arg(long)]
+    pub hole_id: Option<String>,
+
+    /// The orientation line used for measurements without one: top, bottom or an offset angle from the top [default: top]
     #[arg(long)]
-    pub dh_measurements: String,
+    pub orientation_line: Option<BHOrientationLine>,
+
+    /// The convention used for the inclinations in the orientation file [default: negative-down]
+    #[arg(long, value_enum)]
+    pub inclination_convention: Option<InclinationConvention>,
+
+    #[command(flatten)]
+    pub beta_convention: BetaConvention,
+
+    #[command(flatten)]
+    pub input: CsvInput,
+
+    #[command(flatten)]
+    pub uncertainty: Uncertainty,
+
+    #[command(flatten)]
+    pub survey_qa: SurveyQa,
+
+    #[command(flatten)]
+    pub survey_smoothing: SurveySmoothing,
 
-    /// Path to where the output CSV file should be written
+    /// Path to where the output file should be written, `-` writes to stdout [default: -].
+    /// Takes precedence over the output files of single holes in the configuration file.
     #[arg(short, long)]
     pub output: Option<String>,
+
+    /// The format of the output file [default: csv]
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// The north the output bearings, planes and uncertainties refer to [default: true]
+    #[arg(long, value_enum)]
+    pub output_north: Option<AzimuthDatum>,
+
+    /// Only print errors, no warnings or summary
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+fn parse_survey_datum(s: &str) -> Result<(String, AzimuthDatum), String> {
+    match s.split_once('=') {
+        Some((survey_type, datum)) if !survey_type.is_empty() => Ok((
+            survey_type.to_string(),
+            AzimuthDatum::from_str(datum, true)?,
+        )),
+        _ => Err(format!(
+            "Expected a survey type and azimuth datum like gyro=true, got {s}"
+        )),
+    }
+}
+
+/// Counts of what happened to each hole
+struct HoleSummary {
+    hole_id: String,
+    stations: usize,
+    measurements: usize,
+    oriented: usize,
+    unoriented: usize,
+    failed: usize,
+    /// Measurements deeper than the end of hole of the collar, counted rather than kept as issues while streaming
+    past_end_of_hole: usize,
+    deepest: f64,
+    error: Option<String>,
+}
+
+impl HoleSummary {
+    fn new(hole_id: &str, stations: usize) -> Self {
+        Self {
+            hole_id: hole_id.to_string(),
+            stations,
+            measurements: 0,
+            oriented: 0,
+            unoriented: 0,
+            failed: 0,
+            past_end_of_hole: 0,
+            deepest: 0.0,
+            error: None,
+        }
+    }
 }
 
+/// Orients the measurements as they are read so that only the surveys, runs and collars are held in memory.
 pub fn borehole(cmd: Borehole) {
-    let mut ori_rdr = csv::Reader::from_path(cmd.dh_orientation).unwrap();
-    let hole_orientations = ori_rdr
-        .deserialize()
+    let config = Config::load(cmd.config.as_deref()).unwrap_or_else(|error| exit_with_error(error));
+    let input = cmd.input.clone().or(config
+        .csv_input()
+        .unwrap_or_else(|error| exit_with_error(error)));
+    let mut surveys = cmd
+        .dh_orientation
+        .iter()
+        .map(|source| match source.split_once('=') {
+            Some((survey_type, path)) => (Some(survey_type.to_string()), path.to_string()),
+            None => (None, source.clone()),
+        })
+        .collect::<Vec<(Option<String>, String)>>();
+    if surveys.is_empty() {
+        surveys.extend(config.input.orientation.clone().map(|path| (None, path)));
+        surveys.extend(
+            config
+                .input
+                .surveys
+                .iter()
+                .map(|(survey_type, path)| (Some(survey_type.clone()), path.clone())),
+        );
+    }
+    if surveys.is_empty() {
+        exit_with_error("No orientation file, use --dh-orientation or set input.orientation in the configuration".to_string());
+    }
+    let mut wells = vec![];
+    // Stations with the path of the LAS file they were read from
+    let stations = surveys
+        .iter()
+        .flat_map(|(survey_type, path)| {
+            let las = is_las(path).then_some(path);
+            let stations = match las.is_some() {
+                true => {
+                    let survey = read_las(path).unwrap_or_else(|error| exit_with_error(error));
+                    if survey.null_rows > 0 && !cmd.quiet {
+                        eprintln!(
+                            "Warning: {path}: depths skipped for null values: {}",
+                            survey.null_rows
+                        );
+                    }
+                    wells.extend(survey.well);
+                    survey.stations
+                }
+                false => input.read::<BHOrientation>(path),
+            };
+            stations.into_iter().map(move |station| {
+                let station = BHOrientation {
+                    survey_type: station.survey_type.or(survey_type.clone()),
+                    ..station
+                };
+                (station, las)
+            })
+        })
+        .collect::<Vec<(BHOrientation, Option<&String>)>>();
+    let default_hole_id = cmd
+        .hole_id
+        .clone()
+        .or(config.hole_id.clone())
+        .or(wells.first().cloned())
+        .unwrap_or_else(|| "dh123".to_string());
+    let measurements_path = cmd
+        .dh_measurements
+        .clone()
+        .or(cmd.measurements.clone())
+        .or(config.input.measurements.clone())
+        .unwrap_or_else(|| {
+            exit_with_error("No measurements file, use --dh-measurements or set input.measurements in the configuration".to_string())
+        });
+    let flags = Conventions {
+        orientation_line: cmd.orientation_line,
+        inclination: cmd.inclination_convention,
+        beta: cmd.beta_convention,
+        azimuth_datum: cmd.azimuth_datum,
+        declination: cmd.declination,
+    };
+    // LAS deviation logs record inclinations from vertical, they are converted to the default convention
+    // unless a convention is set for the hole
+    let stations = stations
         .into_iter()
-        .map(|result| {
-            let record: BHOrientation = result.unwrap();
-            record
+        .map(|(station, las)| {
+            let hole_id = station.hole_id.as_ref().unwrap_or(&default_hole_id);
+            let Some(path) =
+                las.filter(|_| flags.or(config.conventions(hole_id)).inclination.is_none())
+            else {
+                return station;
+            };
+            let inclination = GCInclinationConvention::FromVertical
+                .try_to_negative_down(station.inclination)
+                .unwrap_or_else(|error| {
+                    exit_with_error(format!("{path}: inclination at {}: {error}", station.depth))
+                });
+            BHOrientation {
+                inclination: GCInclinationConvention::from(InclinationConvention::default())
+                    .from_negative_down(inclination),
+                ..station
+            }
         })
-        .collect();
+        .collect::<Vec<BHOrientation>>();
+    let format = cmd.format.or(config.output.format).unwrap_or_default();
+    let output_path = |hole_id: &str| {
+        cmd.output
+            .clone()
+            .or(config
+                .holes
+                .get(hole_id)
+                .and_then(|hole| hole.output.clone()))
+            .or(config.output.path.clone())
+            .unwrap_or_else(|| "-".to_string())
+    };
 
-    let mut ori_rdr = csv::Reader::from_path(cmd.dh_measurements).unwrap();
-    let raw_measurements = ori_rdr
-        .deserialize()
+    let mut project = Project::new(
+        match cmd.dh_collars.as_ref().or(config.input.collars.as_ref()) {
+            Some(path) => input.read::<Collar>(path),
+            None => vec![],
+        },
+        stations,
+        vec![],
+        match cmd.dh_runs.as_ref().or(config.input.runs.as_ref()) {
+            Some(path) => input.read::<CoreRun>(path),
+            None => vec![],
+        },
+        &default_hole_id,
+    );
+    let interval_tables = match cmd.dh_intervals.is_empty() {
+        true => config.input.intervals.clone(),
+        false => cmd
+            .dh_intervals
+            .iter()
+            .map(|table| {
+                table
+                    .split_once('=')
+                    .map(|(name, path)| (name.to_string(), path.to_string()))
+                    .unwrap_or_else(|| {
+                        exit_with_error(format!("{table}: interval tables are given as NAME=PATH"))
+                    })
+            })
+            .collect(),
+    };
+    let survey_datums = match cmd.survey_datum.is_empty() {
+        true => config.input.survey_datums.clone(),
+        false => cmd.survey_datum.iter().cloned().collect(),
+    };
+    project.survey_datums = survey_datums
         .into_iter()
-        .map(|result| {
-            let record: RawMeasurement = result.unwrap();
-            record
-        })
+        .map(|(survey_type, datum)| (survey_type, datum.into()))
         .collect();
+    for (name, path) in &interval_tables {
+        project.add_interval_table(name, input.read_intervals(path, &default_hole_id));
+    }
+    if let Some(path) = cmd
+        .magnetic_model
+        .as_ref()
+        .or(config.input.magnetic_model.as_ref())
+    {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|error| exit_with_error(format!("{path}: {error}")));
+        project.magnetic_model = Some(
+            MagneticModel::from_cof(&text)
+                .unwrap_or_else(|error| exit_with_error(format!("{path}: {error}"))),
+        );
+    }
+    project.survey_priority = cmd
+        .survey_priority
+        .clone()
+        .unwrap_or(config.input.survey_priority.clone());
+    project.utm_zone = cmd.utm_zone.or(config.utm_zone);
+    if matches!(format, Format::Geojson)
+        && project.utm_zone.is_none()
+        && !project.collars.is_empty()
+        && !cmd.quiet
+    {
+        eprintln!("Warning: GeoJSON points are longitude and latitude, the measurements have no geometry without --utm-zone");
+    }
+    project.local_grid = cmd
+        .local_grid
+        .clone()
+        .or(config.local_grid.clone())
+        .transform(&input, cmd.quiet)
+        .unwrap_or_else(|error| exit_with_error(error));
+    let survey_smoothing = cmd
+        .survey_smoothing
+        .clone()
+        .or(config.survey_smoothing.clone());
+    if survey_smoothing.is_enabled() {
+        project.survey_smoothing = Some(
+            survey_smoothing
+                .smoothing()
+                .unwrap_or_else(|error| exit_with_error(error)),
+        );
+    }
+    project.output_north = cmd
+        .output_north
+        .or(config.output.north)
+        .unwrap_or_default()
+        .into();
+    for hole_id in project.hole_ids() {
+        let conventions = flags.or(config.conventions(&hole_id));
+        if conventions.azimuth_datum.is_some() || conventions.declination.is_some() {
+            project.azimuth_references.insert(
+                hole_id,
+                AzimuthReference {
+                    datum: conventions.azimuth_datum.map(Into::into),
+                    declination: conventions.declination,
+                },
+            );
+        }
+    }
+    let mut issues = project.validate();
+    let survey_qa = cmd.survey_qa.clone().or(config.survey_qa.clone());
 
-    let dh123 = GCBorehole::new(BHOrientationLine::Top, raw_measurements, hole_orientations);
-    println!("{:#?}", dh123.oriented_measurements);
-
-    match cmd.output {
-        Some(path) => {
-            let file = File::create(&path).unwrap();
-            let mut writer = csv::Writer::from_writer(file);
-
-            #[rustfmt::skip]
-            writer.write_record(["strike", "dip", "dip_direction", "pole.trend", "pole.plunge"]).unwrap();
-            for measurement in dh123.oriented_measurements {
-                writer
-                    .write_record([
-                        measurement.strike.to_string(),
-                        measurement.dip.to_string(),
-                        measurement.dip_direction.to_string(),
-                        measurement.pole.trend.to_string(),
-                        measurement.pole.plunge.to_string(),
-                    ])
-                    .unwrap();
+    let mut boreholes: BTreeMap<String, GCBorehole> = BTreeMap::new();
+    let mut summaries: BTreeMap<String, HoleSummary> = BTreeMap::new();
+    // Holes without a survey are reported by `validate`
+    for hole_id in project.surveys.keys().cloned() {
+        let mut summary =
+            HoleSummary::new(&hole_id, project.surveys.get(&hole_id).map_or(0, Vec::len));
+        let conventions = flags.or(config.conventions(&hole_id));
+        match project.borehole(
+            &hole_id,
+            conventions.orientation_line.unwrap_or_default(),
+            conventions.inclination.unwrap_or_default().into(),
+            conventions.beta.into(),
+        ) {
+            Ok(mut borehole) => {
+                if survey_qa.is_enabled() {
+                    borehole.check_survey(&survey_qa.limits());
+                }
+                boreholes.insert(hole_id.clone(), borehole);
             }
-            writer.flush().unwrap();
-            println!("Output written to: {path}")
+            Err(error) => summary.error = Some(error),
         }
-        None => {
-            println!(
-                "No output file specified.\nUse the --output flag to specify an output file.\nExiting."
-            );
-            std::process::exit(0);
+        summaries.insert(hole_id, summary);
+    }
+
+    let (extra_columns, measurements) = input.read_measurements(&measurements_path);
+    // Holes can be written to separate files, the writers are opened as they are needed
+    let mut writers: BTreeMap<String, MeasurementWriter<Box<dyn Write>>> = BTreeMap::new();
+    for measurement in measurements {
+        let hole_id = measurement
+            .hole_id
+            .clone()
+            .unwrap_or_else(|| default_hole_id.clone());
+        let summary = summaries.entry(hole_id.clone()).or_insert_with(|| {
+            let mut summary = HoleSummary::new(&hole_id, 0);
+            summary.error = Some("No survey for this hole".to_string());
+            summary
+        });
+        if summary.measurements == 0
+            && !project.collars.is_empty()
+            && !project.collars.contains_key(&hole_id)
+        {
+            issues.push(IntegrityIssue::MissingCollar {
+                hole_id: hole_id.clone(),
+                table: "measurement".to_string(),
+            });
+        }
+        summary.measurements += 1;
+        if let Some(collar) = project.collars.get(&hole_id) {
+            if measurement.depth > collar.eoh {
+                summary.past_end_of_hole += 1;
+                summary.deepest = summary.deepest.max(measurement.depth);
+            }
         }
+
+        let Some(borehole) = boreholes.get(&hole_id) else {
+            summary.failed += 1;
+            continue;
+        };
+        match borehole.orient(measurement) {
+            Ok(mut oriented) => {
+                if cmd.uncertainty.is_enabled() {
+                    match cmd.uncertainty.uncertainty_method {
+                        // The seed is offset by the index of the measurement in the hole
+                        Method::MonteCarlo => oriented.estimate_uncertainty(
+                            &cmd.uncertainty.errors(),
+                            cmd.uncertainty.samples,
+                            cmd.uncertainty
+                                .seed
+                                .wrapping_add((summary.oriented + summary.unoriented) as u64),
+                        ),
+                        Method::Analytical => oriented.propagate_errors(&cmd.uncertainty.errors()),
+                    }
+                }
+                match oriented.is_oriented() {
+                    true => summary.oriented += 1,
+                    false => summary.unoriented += 1,
+                }
+                let path = output_path(&hole_id);
+                writers
+                    .entry(path)
+                    .or_insert_with_key(|path| {
+                        MeasurementWriter::new(
+                            format,
+                            open_output(path),
+                            extra_columns.clone(),
+                            project.utm_zone,
+                        )
+                    })
+                    .write(&hole_id, &oriented);
+            }
+            Err(failure) => {
+                summary.failed += 1;
+                if !cmd.quiet {
+                    eprintln!("Warning: {hole_id}: {failure}");
+                }
+            }
+        }
+    }
+    let paths = writers.keys().cloned().collect::<Vec<String>>();
+    for writer in writers.into_values() {
+        writer.finish();
+    }
+
+    if let Some(path) = cmd
+        .survey_merge_report
+        .as_ref()
+        .or(config.output.survey_merge_report.as_ref())
+    {
+        write_disagreements(
+            open_output(path),
+            boreholes
+                .iter()
+                .map(|(hole_id, borehole)| (hole_id, borehole.survey_disagreements.as_slice())),
+        );
+    }
+    if let Some(path) = &survey_qa.report {
+        write_report(
+            open_output(path),
+            boreholes
+                .iter()
+                .map(|(hole_id, borehole)| (hole_id, borehole.survey_qa.as_slice())),
+        );
+    }
+
+    if let Some(path) = &survey_smoothing.output {
+        survey_smoothing::write_survey(open_output(path), boreholes.iter());
+    }
+    if let Some(path) = &survey_smoothing.report {
+        survey_smoothing::write_report(open_output(path), boreholes.iter());
+    }
+
+    if cmd.quiet {
+        for summary in summaries.values() {
+            if let Some(error) = &summary.error {
+                eprintln!("{}: {error}", summary.hole_id);
+            }
+        }
+    } else {
+        for issue in issues {
+            eprintln!("Warning: {issue}");
+        }
+        for summary in summaries
+            .values()
+            .filter(|summary| summary.past_end_of_hole > 0)
+        {
+            let Some(collar) = project.collars.get(&summary.hole_id) else {
+                continue;
+            };
+            match summary.past_end_of_hole {
+                1 => eprintln!(
+                    "Warning: {}",
+                    IntegrityIssue::PastEndOfHole {
+                        hole_id: summary.hole_id.clone(),
+                        table: "measurement".to_string(),
+                        depth: summary.deepest,
+                        eoh: collar.eoh,
+                    }
+                ),
+                count => eprintln!(
+                    "Warning: {}: {count} measurements are past the end of hole at {}, the deepest at {}",
+                    summary.hole_id, collar.eoh, summary.deepest
+                ),
+            }
+        }
+        for (hole_id, borehole) in boreholes.iter() {
+            print_disagreements(hole_id, &borehole.survey_disagreements);
+            for station in borehole
+                .smoothed_survey
+                .iter()
+                .filter(|station| station.rejected)
+            {
+                eprintln!(
+                    "Warning: {hole_id}: survey station at {} was rejected, it is {:.2}° from the smoothed survey",
+                    station.depth, station.change
+                );
+            }
+            for station in borehole
+                .survey_qa
+                .iter()
+                .filter(|station| station.is_flagged())
+            {
+                eprintln!(
+                    "Warning: {hole_id}: survey station at {} is suspect: {}",
+                    station.depth,
+                    join_flags(&station.flags)
+                );
+            }
+        }
+        print_summary(summaries.values());
+        for path in paths.iter().filter(|path| *path != "-") {
+            eprintln!("Output written to: {path}");
+        }
+        if let Some(path) = survey_qa.report.as_ref().filter(|path| *path != "-") {
+            eprintln!("Survey QA written to: {path}");
+        }
+        if let Some(path) = survey_smoothing.output.as_ref().filter(|path| *path != "-") {
+            eprintln!("Smoothed survey written to: {path}");
+        }
+    }
+}
+
+fn print_summary<'a>(summaries: impl Iterator<Item = &'a HoleSummary>) {
+    eprintln!(
+        "{:<12} {:>8} {:>12} {:>8} {:>10} {:>6}",
+        "hole_id", "stations", "measurements", "oriented", "unoriented", "failed"
+    );
+    for summary in summaries {
+        eprintln!(
+            "{:<12} {:>8} {:>12} {:>8} {:>10} {:>6}{}",
+            summary.hole_id,
+            summary.stations,
+            summary.measurements,
+            summary.oriented,
+            summary.unoriented,
+            summary.failed,
+            match &summary.error {
+                Some(error) => format!("  {error}"),
+                None => String::new(),
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use geocalc::Plane;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        borehole: Borehole,
+    }
+
+    const LAS: &str = "~Version information
+ VERS.                 2.0 : CWLS LOG ASCII STANDARD - VERSION 2.0
+ WRAP.                  NO : ONE LINE PER DEPTH STEP
+~Well information
+ NULL.             -999.25 : NULL VALUE
+ WELL.                  S1 : WELL
+~Curve information
+ DEPT.M                    : MEASURED DEPTH
+ AZIM.DEG                  : AZIMUTH
+ INCL.DEG                  : INCLINATION
+~A
+    0.000   120.00   30.00
+  100.000   120.00   30.00
+";
+
+    /// Run the borehole command on a LAS survey and one measurement, returns the output row
+    fn orient_with_las(args: &str) -> csv::StringRecord {
+        let dir = std::env::temp_dir().join(format!("geocalc-las-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        fs::write(path("s.las"), LAS).unwrap();
+        fs::write(path("m.csv"), "hole_id,depth,alpha,beta\nS1,50,40,200\n").unwrap();
+        let cmd = Cli::try_parse_from(
+            format!(
+                "borehole --dh-orientation {} --dh-measurements {} -o {} -q {args}",
+                path("s.las"),
+                path("m.csv"),
+                path("o.csv")
+            )
+            .split_whitespace(),
+        )
+        .unwrap()
+        .borehole;
+        borehole(cmd);
+        let mut reader = csv::Reader::from_path(path("o.csv")).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let record = reader.records().next().unwrap().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        headers
+            .iter()
+            .zip(record.iter())
+            .filter(|(header, _)| ["inclination", "dip", "dip_direction"].contains(header))
+            .map(|(_, field)| field)
+            .collect()
+    }
+
+    #[test]
+    fn borehole_las_inclinations_are_from_vertical() {
+        // INCL 30 is 30° from vertical, a hole plunging 60°
+        let plane = Plane::alpha_beta(120.0, -60.0, 40.0, 200.0, BHOrientationLine::Top);
+        let expected = csv::StringRecord::from(vec![
+            "-60".to_string(),
+            plane.dip.to_string(),
+            plane.dip_direction.to_string(),
+        ]);
+        assert_eq!(orient_with_las(""), expected);
+
+        // A convention set for the hole is used as given, the output is negative down
+        let plane = Plane::alpha_beta(120.0, -30.0, 40.0, 200.0, BHOrientationLine::Top);
+        assert_eq!(
+            orient_with_las("--inclination-convention positive-down"),
+            csv::StringRecord::from(vec![
+                "-30".to_string(),
+                plane.dip.to_string(),
+                plane.dip_direction.to_string(),
+            ])
+        );
     }
 }
@@ -0,0 +1,362 @@
+use clap::Args;
+use geocalc::{BHOrientationLine, UtmZone};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use super::{
+    conventions::{AzimuthDatum, BetaConvention, InclinationConvention},
+    input::{parse_delimiter, CsvInput, LengthUnit},
+    local_grid::LocalGrid,
+    output::Format,
+    survey_qa::SurveyQa,
+    survey_smoothing::SurveySmoothing,
+};
+
+/// The configuration file looked for in the working directory
+pub const DEFAULT_CONFIG: &str = "geocalc.toml";
+
+/// A project configuration file, see `TEMPLATE` for the layout.
+/// Command line flags take precedence over every value in it.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The hole id used for rows without a hole_id column
+    pub hole_id: Option<String>,
+    /// The UTM zone of the collar coordinates
+    pub utm_zone: Option<UtmZone>,
+    pub input: InputConfig,
+    pub conventions: Conventions,
+    pub output: OutputConfig,
+    /// The transform from the local mine grid to the UTM grid
+    pub local_grid: LocalGrid,
+    pub survey_qa: SurveyQa,
+    pub survey_smoothing: SurveySmoothing,
+    /// Settings of single holes that take precedence over the project settings
+    pub holes: BTreeMap<String, HoleConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct InputConfig {
+    pub orientation: Option<String>,
+    /// Surveys by survey type, merged with `orientation` by `survey_priority`
+    pub surveys: BTreeMap<String, String>,
+    /// The north reference of the bearings by survey type
+    pub survey_datums: BTreeMap<String, AzimuthDatum>,
+    /// Survey types from the most to the least trusted
+    pub survey_priority: Vec<String>,
+    pub measurements: Option<String>,
+    pub runs: Option<String>,
+    pub collars: Option<String>,
+    /// Interval tables by name, e.g. lithology
+    pub intervals: BTreeMap<String, String>,
+    /// A geomagnetic model coefficient file in the NOAA .COF format
+    pub magnetic_model: Option<String>,
+    pub delimiter: Option<String>,
+    pub decimal_comma: Option<bool>,
+    pub length_unit: Option<LengthUnit>,
+    /// Input column names by the name geocalc expects
+    pub columns: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+pub struct Conventions {
+    pub orientation_line: Option<BHOrientationLine>,
+    pub inclination: Option<InclinationConvention>,
+    #[serde(flatten)]
+    pub beta: BetaConvention,
+    pub azimuth_datum: Option<AzimuthDatum>,
+    pub declination: Option<f64>,
+}
+
+impl Conventions {
+    /// Fill the unset values from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            orientation_line: self.orientation_line.or(fallback.orientation_line),
+            inclination: self.inclination.or(fallback.inclination),
+            beta: self.beta.or(fallback.beta),
+            azimuth_datum: self.azimuth_datum.or(fallback.azimuth_datum),
+            declination: self.declination.or(fallback.declination),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub path: Option<String>,
+    pub format: Option<Format>,
+    /// The north the output azimuths refer to
+    pub north: Option<AzimuthDatum>,
+    /// Where the disagreement between merged surveys is written
+    pub survey_merge_report: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct HoleConfig {
+    #[serde(flatten)]
+    pub conventions: Conventions,
+    /// Where the measurements of the hole are written instead of the project output
+    pub output: Option<String>,
+}
+
+impl Config {
+    /// Load the configuration from `path`, or from `geocalc.toml` in the working directory when it exists.
+    /// Relative paths in the file are taken relative to the file.
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG).exists() => DEFAULT_CONFIG,
+            None => return Ok(Self::default()),
+        };
+        let text = fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+        let mut config: Self = toml::from_str(&text).map_err(|error| format!("{path}: {error}"))?;
+
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        let input = &mut config.input;
+        for file in [
+            &mut input.orientation,
+            &mut input.measurements,
+            &mut input.runs,
+            &mut input.collars,
+            &mut input.magnetic_model,
+            &mut config.local_grid.control,
+            &mut config.survey_qa.report,
+            &mut config.survey_smoothing.output,
+            &mut config.survey_smoothing.report,
+            &mut config.output.path,
+            &mut config.output.survey_merge_report,
+        ]
+        .into_iter()
+        .chain(config.holes.values_mut().map(|hole| &mut hole.output))
+        .flatten()
+        .chain(input.surveys.values_mut())
+        .chain(input.intervals.values_mut())
+        {
+            if file != "-" {
+                *file = dir.join(&*file).to_string_lossy().into_owned();
+            }
+        }
+        Ok(config)
+    }
+
+    /// The input layout of the configuration file
+    pub fn csv_input(&self) -> Result<CsvInput, String> {
+        Ok(CsvInput {
+            delimiter: self
+                .input
+                .delimiter
+                .as_deref()
+                .map(parse_delimiter)
+                .transpose()?,
+            decimal_comma: self.input.decimal_comma,
+            columns: self
+                .input
+                .columns
+                .iter()
+                .map(|(from, to)| (from.clone(), to.clone()))
+                .collect(),
+            length_unit: self.input.length_unit,
+        })
+    }
+
+    /// The conventions of a hole, falling back to the project conventions
+    pub fn conventions(&self, hole_id: &str) -> Conventions {
+        match self.holes.get(hole_id) {
+            Some(hole) => hole.conventions.or(self.conventions),
+            None => self.conventions,
+        }
+    }
+}
+
+/// A commented configuration file with every setting
+pub const TEMPLATE: &str = r#"# geocalc project configuration
+# Command line flags take precedence over the values in this file.
+# Relative paths are relative to this file, "-" is stdin or stdout.
+
+# The hole id used for rows without a hole_id column
+# hole_id = "dh123"
+# The UTM zone of the collar coordinates, needed for grid bearings and grid north output
+# utm_zone = "55S"
+
+[input]
+# hole_id[,survey_type],depth,bearing,inclination, or a LAS 2.0 deviation log (.las) whose
+# inclinations are taken from vertical unless an inclination convention is set
+orientation = "survey.csv"
+# Surveys of different types are merged by survey_priority, each survey is used over its
+# depth range where no more trusted survey covers the hole
+# survey_priority = ["gyro", "multishot", "magnetic", "planned"]
+# hole_id,depth,alpha,beta[,orientation_line], other columns are copied to the output
+measurements = "structures.csv"
+# hole_id,from,to,confidence[,orientation_line]
+# runs = "runs.csv"
+# hole_id,x,y,z,eoh[,date][,azimuth_datum][,declination][,latitude,longitude]
+# collars = "collars.csv"
+# A geomagnetic model coefficient file (.COF) in the WMM layout that replaces the bundled WMM2025,
+# used for the declination of magnetic surveys from the collar location and date.
+# WMM2025 covers 2025.0 to 2030.0, older holes need an earlier model (e.g. IGRF) or a declination.
+# magnetic_model = "WMM.COF"
+
+# A single character, "tab" or "semicolon"
+# delimiter = ","
+# decimal_comma = false
+# The unit of depths and collar coordinates: "metres" or "feet"
+# length_unit = "metres"
+
+# Surveys by survey type, in addition to orientation
+[input.surveys]
+# gyro = "gyro.csv"
+# magnetic = "single_shots.csv"
+
+# The north reference of the bearings of each survey type: "true", "magnetic", "grid" or "local",
+# takes precedence over azimuth_datum. Each survey is referred to true north before they are merged.
+[input.survey_datums]
+# gyro = "true"
+# magnetic = "magnetic"
+
+# Interval tables by name: hole_id,from,to[,...], checked against the collars and surveys
+[input.intervals]
+# lithology = "lithology.csv"
+
+# Input column names mapped to the names geocalc expects
+[input.columns]
+# HOLEID = "hole_id"
+# DEPTH_M = "depth"
+# AZIMUTH = "bearing"
+# DIP = "inclination"
+# ALPHA_DEG = "alpha"
+# BETA_DEG = "beta"
+
+[conventions]
+# "top", "bottom" or an offset angle from the top
+# orientation_line = "top"
+# "negative-down", "positive-down" or "from-vertical"
+# inclination = "negative-down"
+# "clockwise-down-hole" or "counter-clockwise-down-hole"
+# beta_direction = "clockwise-down-hole"
+# "lower" or "upper"
+# beta_apex = "lower"
+# The north reference of the survey bearings: "true", "magnetic", "grid" or "local",
+# takes precedence over the azimuth_datum of the collars
+# azimuth_datum = "true"
+# The declination in degrees (positive east) used for magnetic bearings
+# declination = 0.0
+
+[output]
+# path = "oriented.csv"
+# "csv", "json", "ndjson" or "geojson"
+# format = "csv"
+# The north the output bearings, planes and uncertainties refer to: "true", "magnetic", "grid" or "local"
+# north = "true"
+# Where the disagreement between merged surveys is written
+# survey_merge_report = "survey_merge.csv"
+
+# A local mine grid for "local" azimuths, either fitted to control points or given by its
+# rotation, scale and origin. Control points: [name,]local_x,local_y,x,y
+[local_grid]
+# control = "control_points.csv"
+# "similarity" or "affine"
+# kind = "similarity"
+# The UTM grid bearing of local grid north in degrees
+# rotation = 0.0
+# scale = 1.0
+# local_origin = [10000.0, 5000.0]
+# map_origin = [650000.0, 5800000.0]
+
+# Checks of the surveys for doglegs, jumps and stations that disagree with their neighbours.
+# Measurements oriented with a flagged station are flagged in the output.
+[survey_qa]
+# enabled = false
+# Limits in degrees, the dogleg severity per 30 m
+# dogleg_severity = 3.0
+# bearing_jump = 5.0
+# inclination_jump = 3.0
+# trend_deviation = 2.0
+# Where the checks of every station are written
+# report = "survey_qa.csv"
+
+# Smoothing of the surveys with a median filter on the direction of the hole before the
+# measurements are oriented. Stations far from the median are removed, except the first
+# which is replaced by the median.
+[survey_smoothing]
+# enabled = false
+# The number of stations the median is taken over
+# window = 5
+# Stations further than this angle in degrees from the median are removed
+# rejection_angle = 3.0
+# Replace outlying stations by the median instead of removing them
+# keep_outliers = false
+# Where the smoothed surveys are written, in the layout of the orientation input
+# output = "smoothed_survey.csv"
+# Where what smoothing did to every station is written
+# report = "survey_smoothing.csv"
+
+# Settings of single holes, any of the conventions and a separate output file
+# [holes.DH001]
+# orientation_line = "bottom"
+# declination = 11.5
+# output = "DH001.csv"
+"#;
+
+#[derive(Args)]
+pub struct Init {
+    /// Where to write the configuration file
+    #[arg(default_value = DEFAULT_CONFIG)]
+    pub path: String,
+
+    /// Overwrite the file if it exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+pub fn init(cmd: Init) {
+    if Path::new(&cmd.path).exists() && !cmd.force {
+        eprintln!("{} already exists, use --force to overwrite it", cmd.path);
+        std::process::exit(1);
+    }
+    fs::write(&cmd.path, TEMPLATE).unwrap();
+    eprintln!("Configuration written to: {}", cmd.path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_paths_are_relative_to_the_file() {
+        let dir = std::env::temp_dir().join(format!("geocalc-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("geocalc.toml");
+        fs::write(
+            &path,
+            r#"
+[input]
+measurements = "data/measurements.csv"
+orientation = "-"
+surveys = { gyro = "gyro.csv" }
+
+[output]
+path = "-"
+
+[holes.DH1]
+output = "/abs/DH1.csv"
+"#,
+        )
+        .unwrap();
+        let config = Config::load(Some(&path.to_string_lossy())).unwrap();
+        let in_dir = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        assert_eq!(
+            config.input.measurements,
+            Some(in_dir("data/measurements.csv"))
+        );
+        assert_eq!(config.input.surveys["gyro"], in_dir("gyro.csv"));
+        assert_eq!(config.input.orientation.as_deref(), Some("-"));
+        assert_eq!(config.output.path.as_deref(), Some("-"));
+        assert_eq!(config.holes["DH1"].output.as_deref(), Some("/abs/DH1.csv"));
+
+        assert!(Config::load(Some(&dir.join("missing.toml").to_string_lossy())).is_err());
+    }
+}
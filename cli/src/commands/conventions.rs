@@ -0,0 +1,110 @@
+use clap::{Args, ValueEnum};
+use geocalc::{
+    AzimuthDatum as GCAzimuthDatum, BetaApex as GCBetaApex, BetaConvention as GCBetaConvention,
+    BetaDirection as GCBetaDirection, InclinationConvention as GCInclinationConvention,
+};
+use serde::Deserialize;
+
+/// How the inclination of the hole was recorded
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum InclinationConvention {
+    /// Angle from horizontal, negative when the hole points down
+    #[default]
+    NegativeDown,
+    /// Angle from horizontal, positive when the hole points down
+    PositiveDown,
+    /// Angle from vertical (zenith angle), 0 is straight down
+    FromVertical,
+}
+
+impl From<InclinationConvention> for GCInclinationConvention {
+    fn from(convention: InclinationConvention) -> Self {
+        match convention {
+            InclinationConvention::NegativeDown => Self::NegativeDown,
+            InclinationConvention::PositiveDown => Self::PositiveDown,
+            InclinationConvention::FromVertical => Self::FromVertical,
+        }
+    }
+}
+
+/// The north reference of the survey bearings
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum AzimuthDatum {
+    #[default]
+    True,
+    Magnetic,
+    Grid,
+    /// A local mine grid, see the --grid-* options
+    Local,
+}
+
+impl From<AzimuthDatum> for GCAzimuthDatum {
+    fn from(datum: AzimuthDatum) -> Self {
+        match datum {
+            AzimuthDatum::True => Self::True,
+            AzimuthDatum::Magnetic => Self::Magnetic,
+            AzimuthDatum::Grid => Self::Grid,
+            AzimuthDatum::Local => Self::Local,
+        }
+    }
+}
+
+/// The direction beta was measured in
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum BetaDirection {
+    /// Clockwise looking down the hole (counter-clockwise looking up the hole)
+    #[default]
+    ClockwiseDownHole,
+    /// Counter-clockwise looking down the hole (clockwise looking up the hole)
+    CounterClockwiseDownHole,
+}
+
+/// The apex of the structure's ellipse that beta was measured to
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum BetaApex {
+    /// The point of the ellipse furthest down the hole
+    #[default]
+    Lower,
+    /// The point of the ellipse furthest up the hole
+    Upper,
+}
+
+/// Unset values fall back to the project configuration and then the default
+#[derive(Args, Deserialize, Clone, Copy, Default)]
+pub struct BetaConvention {
+    /// The direction beta was measured in [default: clockwise-down-hole]
+    #[arg(long, value_enum)]
+    pub beta_direction: Option<BetaDirection>,
+
+    /// The apex of the structure's ellipse that beta was measured to [default: lower]
+    #[arg(long, value_enum)]
+    pub beta_apex: Option<BetaApex>,
+}
+
+impl BetaConvention {
+    /// Fill the unset values from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            beta_direction: self.beta_direction.or(fallback.beta_direction),
+            beta_apex: self.beta_apex.or(fallback.beta_apex),
+        }
+    }
+}
+
+impl From<BetaConvention> for GCBetaConvention {
+    fn from(convention: BetaConvention) -> Self {
+        let direction = match convention.beta_direction.unwrap_or_default() {
+            BetaDirection::ClockwiseDownHole => GCBetaDirection::ClockwiseDownHole,
+            BetaDirection::CounterClockwiseDownHole => GCBetaDirection::CounterClockwiseDownHole,
+        };
+        let apex = match convention.beta_apex.unwrap_or_default() {
+            BetaApex::Lower => GCBetaApex::Lower,
+            BetaApex::Upper => GCBetaApex::Upper,
+        };
+        Self::new(direction, apex)
+    }
+}
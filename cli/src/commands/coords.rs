@@ -0,0 +1,118 @@
+use clap::Args;
+use csv::StringRecord;
+use geocalc::{geodetic_from_utm, utm_from_geodetic, UtmZone};
+use std::collections::BTreeSet;
+
+use super::{exit_with_error, input::CsvInput, output::open_output};
+
+#[derive(Args)]
+pub struct Coords {
+    /// Path to a csv file or worksheet with latitude and longitude columns, or x and y columns with
+    /// --to-geographic. `-` reads from stdin. Other columns are copied to the output.
+    #[arg(default_value = "-")]
+    pub input: String,
+
+    /// Convert x (easting) and y (northing) to latitude and longitude
+    #[arg(long, requires = "utm_zone")]
+    pub to_geographic: bool,
+
+    /// The UTM zone, e.g. 55S. Detected from each point when converting to UTM.
+    #[arg(long)]
+    pub utm_zone: Option<UtmZone>,
+
+    #[command(flatten)]
+    pub csv_input: CsvInput,
+
+    /// Path to where the output file should be written, `-` writes to stdout
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+}
+
+/// The index of `column` in the headers
+pub fn column_index(headers: &StringRecord, column: &str) -> usize {
+    headers
+        .iter()
+        .position(|header| header == column)
+        .unwrap_or_else(|| exit_with_error(format!("The input has no {column} column")))
+}
+
+/// Parse a coordinate, `row` is the line number in the input
+pub fn parse(record: &StringRecord, index: usize, column: &str, row: usize) -> f64 {
+    record[index].trim().parse().unwrap_or_else(|_| {
+        exit_with_error(format!("Row {row}: invalid {column} `{}`", &record[index]))
+    })
+}
+
+/// Adds the converted columns to every row, existing columns with the same names are replaced
+pub fn coords(cmd: Coords) {
+    let (headers, records) = cmd.csv_input.records(&cmd.input);
+    let (inputs, outputs): ([&str; 2], &[&str]) = match cmd.to_geographic {
+        true => (["x", "y"], &["latitude", "longitude", "convergence"]),
+        false => (
+            ["latitude", "longitude"],
+            &["x", "y", "utm_zone", "convergence"],
+        ),
+    };
+    let inputs = inputs.map(|column| (column, column_index(&headers, column)));
+    let kept = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, header)| !outputs.contains(header))
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+
+    let mut writer = csv::Writer::from_writer(open_output(&cmd.output));
+    writer
+        .write_record(
+            kept.iter()
+                .map(|&index| &headers[index])
+                .chain(outputs.iter().copied()),
+        )
+        .unwrap();
+    let mut zones = BTreeSet::new();
+    for (row, record) in records.enumerate() {
+        // The header is the first line
+        let row = row + 2;
+        let [a, b] = inputs.map(|(column, index)| parse(&record, index, column, row));
+        let converted = match (cmd.to_geographic, cmd.utm_zone) {
+            (true, Some(zone)) => {
+                let (latitude, longitude) = geodetic_from_utm(a, b, zone);
+                let point = utm_from_geodetic(latitude, longitude, zone);
+                vec![
+                    latitude.to_string(),
+                    longitude.to_string(),
+                    point.convergence.to_string(),
+                ]
+            }
+            (true, None) => unreachable!("--to-geographic requires --utm-zone"),
+            (false, zone) => {
+                let zone = zone
+                    .map_or_else(|| UtmZone::containing(a, b), Ok)
+                    .unwrap_or_else(|error| exit_with_error(format!("Row {row}: {error}")));
+                zones.insert(zone.to_string());
+                let point = utm_from_geodetic(a, b, zone);
+                vec![
+                    point.easting.to_string(),
+                    point.northing.to_string(),
+                    zone.to_string(),
+                    point.convergence.to_string(),
+                ]
+            }
+        };
+        writer
+            .write_record(
+                kept.iter()
+                    .map(|&index| &record[index])
+                    .chain(converted.iter().map(String::as_str)),
+            )
+            .unwrap();
+    }
+    writer.flush().unwrap();
+
+    if zones.len() > 1 {
+        eprintln!(
+            "Warning: the points fall in several UTM zones ({}), use --utm-zone to project them all to one zone",
+            zones.into_iter().collect::<Vec<String>>().join(", ")
+        );
+    }
+}
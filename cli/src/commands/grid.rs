@@ -0,0 +1,140 @@
+use clap::Args;
+use geocalc::GridTransform;
+
+use super::{
+    config::Config,
+    coords::{column_index, parse},
+    exit_with_error,
+    input::CsvInput,
+    local_grid::LocalGrid,
+    output::open_output,
+};
+
+/// Columns holding azimuths, these are rotated between the grids
+const AZIMUTH_COLUMNS: [&str; 6] = [
+    "bearing",
+    "strike",
+    "dip_direction",
+    "trend",
+    "pole.trend",
+    "cone.trend",
+];
+
+#[derive(Args)]
+pub struct Grid {
+    /// Path to a csv file or worksheet with local_x and local_y columns, or x and y columns with --to-local.
+    /// `-` reads from stdin. Azimuth columns (bearing, strike, dip_direction, trend, pole.trend and cone.trend)
+    /// are rotated and must refer to UTM grid north with --to-local, e.g. borehole output with --output-north grid,
+    /// and to local grid north otherwise. Other columns are copied to the output.
+    #[arg(default_value = "-")]
+    pub input: String,
+
+    /// Convert UTM x and y to local_x and local_y, the default is from the local grid to UTM
+    #[arg(long)]
+    pub to_local: bool,
+
+    /// Path to the configuration file with the local grid [default: geocalc.toml when it exists]
+    #[arg(long)]
+    pub config: Option<String>,
+
+    #[command(flatten)]
+    pub local_grid: LocalGrid,
+
+    #[command(flatten)]
+    pub csv_input: CsvInput,
+
+    /// Path to where the output file should be written, `-` writes to stdout
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    /// Do not print the residuals of the control points
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// Converts coordinates and azimuths of a table, existing output columns are replaced.
+/// A `north` column, as written by the borehole command, must hold the north the azimuths are converted from
+/// and is updated to the new north.
+pub fn grid(cmd: Grid) {
+    let config = Config::load(cmd.config.as_deref()).unwrap_or_else(|error| exit_with_error(error));
+    let input = cmd.csv_input.clone().or(config
+        .csv_input()
+        .unwrap_or_else(|error| exit_with_error(error)));
+    let transform: GridTransform = cmd
+        .local_grid
+        .clone()
+        .or(config.local_grid.clone())
+        .transform(&input, cmd.quiet)
+        .unwrap_or_else(|error| exit_with_error(error))
+        .unwrap_or_else(|| {
+            exit_with_error("No local grid, use --grid-control or --grid-rotation or set local_grid in the configuration".to_string())
+        });
+
+    let (headers, records) = input.records(&cmd.input);
+    let (inputs, outputs, from_north, north) = match cmd.to_local {
+        true => (["x", "y"], ["local_x", "local_y"], "grid", "local"),
+        false => (["local_x", "local_y"], ["x", "y"], "local", "grid"),
+    };
+    let inputs = inputs.map(|column| (column, column_index(&headers, column)));
+    let kept = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, header)| !outputs.contains(header))
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+
+    let mut writer = csv::Writer::from_writer(open_output(&cmd.output));
+    writer
+        .write_record(
+            kept.iter()
+                .map(|&index| &headers[index])
+                .chain(outputs.iter().copied()),
+        )
+        .unwrap();
+    for (row, record) in records.enumerate() {
+        // The header is the first line
+        let row = row + 2;
+        let mut fields = kept
+            .iter()
+            .map(|&index| {
+                let (header, field) = (&headers[index], &record[index]);
+                match header {
+                    // Unoriented measurements have empty azimuths
+                    _ if field.trim().is_empty() => field.to_string(),
+                    "north" if !field.trim().eq_ignore_ascii_case(from_north) => {
+                        exit_with_error(format!(
+                            "Row {row}: the azimuths refer to {} north, expected {from_north} north",
+                            field.trim()
+                        ))
+                    }
+                    "north" => north.to_string(),
+                    header if AZIMUTH_COLUMNS.contains(&header) => {
+                        let azimuth = parse(&record, index, header, row);
+                        match cmd.to_local {
+                            true => transform.azimuth_to_local(azimuth),
+                            false => transform.azimuth_to_map(azimuth),
+                        }
+                        .to_string()
+                    }
+                    _ => field.to_string(),
+                }
+            })
+            .collect::<Vec<String>>();
+        // Rows without a location, e.g. holes without a collar, are left empty
+        if inputs
+            .iter()
+            .any(|&(_, index)| record[index].trim().is_empty())
+        {
+            fields.extend([String::new(), String::new()]);
+        } else {
+            let [x, y] = inputs.map(|(column, index)| parse(&record, index, column, row));
+            let (x, y) = match cmd.to_local {
+                true => transform.to_local(x, y),
+                false => transform.to_map(x, y),
+            };
+            fields.extend([x.to_string(), y.to_string()]);
+        }
+        writer.write_record(&fields).unwrap();
+    }
+    writer.flush().unwrap();
+}
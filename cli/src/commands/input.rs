@@ -0,0 +1,477 @@
+use calamine::{open_workbook_auto, Data, Reader};
+use clap::{Args, ValueEnum};
+use csv::StringRecord;
+use geocalc::{read_las_survey, Interval, LasSurvey, RawMeasurement};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+};
+
+use super::exit_with_error;
+
+/// Columns geocalc reads as numbers, decimal commas are only converted in these
+const NUMERIC_COLUMNS: [&str; 15] = [
+    "depth",
+    "alpha",
+    "beta",
+    "orientation_line",
+    "bearing",
+    "inclination",
+    "from",
+    "to",
+    "x",
+    "y",
+    "z",
+    "eoh",
+    "declination",
+    "latitude",
+    "longitude",
+];
+
+/// Columns holding lengths, these are converted to metres
+const LENGTH_COLUMNS: [&str; 7] = ["depth", "from", "to", "x", "y", "z", "eoh"];
+
+const MEASUREMENT_COLUMNS: [&str; 5] = ["hole_id", "depth", "alpha", "beta", "orientation_line"];
+
+const INTERVAL_COLUMNS: [&str; 3] = ["hole_id", "from", "to"];
+
+/// The columns of an interval table geocalc reads, the others become the values of the interval
+#[derive(Deserialize)]
+struct IntervalRow {
+    #[serde(default)]
+    hole_id: Option<String>,
+    from: f64,
+    to: f64,
+}
+
+/// The unit of depths and coordinates in the input files
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LengthUnit {
+    #[default]
+    Metres,
+    Feet,
+}
+
+impl LengthUnit {
+    /// The length of the unit in metres
+    fn scale(&self) -> f64 {
+        match self {
+            Self::Metres => 1.0,
+            Self::Feet => 0.3048,
+        }
+    }
+}
+
+/// How the input tables are laid out
+#[derive(Args, Clone, Default)]
+pub struct CsvInput {
+    /// The field delimiter of the input files: a single character, `tab` or `semicolon` [default: ,]
+    #[arg(long, value_parser = parse_delimiter)]
+    pub delimiter: Option<u8>,
+
+    /// Numbers in the input files use a decimal comma, e.g. 12,5.
+    /// --decimal-comma=false overrides the configuration file [default: false]
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub decimal_comma: Option<bool>,
+
+    /// Rename an input column to the name geocalc expects, e.g. --column HOLEID=hole_id --column AZIMUTH=bearing.
+    /// May be given several times.
+    #[arg(long = "column", value_name = "FROM=TO", value_parser = parse_column)]
+    pub columns: Vec<(String, String)>,
+
+    /// The unit of depths and collar coordinates in the input files, they are converted to metres [default: metres]
+    #[arg(long, value_enum)]
+    pub length_unit: Option<LengthUnit>,
+}
+
+pub fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s {
+        "tab" | "\\t" => Ok(b'\t'),
+        "semicolon" => Ok(b';'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(format!(
+            "Unknown delimiter {s}, expected a single character, tab or semicolon"
+        )),
+    }
+}
+
+fn parse_column(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_string(), to.to_string()))
+        }
+        _ => Err(format!(
+            "Expected a column mapping like HOLEID=hole_id, got {s}"
+        )),
+    }
+}
+
+/// Splits a spreadsheet path such as `logs.xlsx#Survey` into the workbook and the worksheet name.
+/// Returns `None` for other files.
+fn split_sheet(path: &str) -> Option<(&str, Option<&str>)> {
+    let is_workbook = |path: &str| {
+        let path = path.to_lowercase();
+        WORKBOOK_EXTENSIONS
+            .iter()
+            .any(|extension| path.ends_with(extension))
+    };
+    match path.rsplit_once('#') {
+        Some((workbook, sheet)) if is_workbook(workbook) => Some((workbook, Some(sheet))),
+        _ if is_workbook(path) => Some((path, None)),
+        _ => None,
+    }
+}
+
+const WORKBOOK_EXTENSIONS: [&str; 5] = [".xlsx", ".xlsm", ".xlsb", ".xls", ".ods"];
+
+/// Read a worksheet, the first one when `sheet` is `None`. The first row holds the column names.
+/// Numbers keep their full precision and dates are written as ISO 8601.
+fn read_sheet(
+    path: &str,
+    sheet: Option<&str>,
+) -> (StringRecord, Box<dyn Iterator<Item = StringRecord>>) {
+    let exit = |error: String| -> ! {
+        eprintln!("Error: {path}: {error}");
+        std::process::exit(1);
+    };
+    let mut workbook = open_workbook_auto(path).unwrap_or_else(|error| exit(error.to_string()));
+    let sheet = match sheet {
+        Some(sheet) => sheet.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| exit("The workbook has no worksheets".to_string())),
+    };
+    let range = workbook
+        .worksheet_range(&sheet)
+        .unwrap_or_else(|error| exit(format!("Worksheet {sheet}: {error}")));
+
+    let mut rows = range
+        .rows()
+        .map(|row| row.iter().map(cell_to_string).collect::<StringRecord>())
+        // Formatted but empty rows at the end of a sheet are common
+        .filter(|row| row.iter().any(|field| !field.is_empty()))
+        .collect::<Vec<StringRecord>>()
+        .into_iter();
+    let headers = rows.next().unwrap_or_default();
+    (headers, Box::new(rows))
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(datetime) => match datetime.as_datetime() {
+            // Written as e.g. 2023-02-14 09:30:00
+            Some(datetime) => {
+                let datetime = datetime.to_string();
+                match datetime.strip_suffix(" 00:00:00") {
+                    Some(date) => date.to_string(),
+                    None => datetime.replacen(' ', "T", 1),
+                }
+            }
+            None => datetime.as_f64().to_string(),
+        },
+        cell => cell.to_string(),
+    }
+}
+
+/// Returns true for LAS well log files, see `read_las`
+pub fn is_las(path: &str) -> bool {
+    path.to_lowercase().ends_with(".las")
+}
+
+/// Read a deviation survey from a LAS file, its depths are always converted by the units in the file
+pub fn read_las(path: &str) -> Result<LasSurvey, String> {
+    // LAS files are often not UTF-8, only the ASCII data matters
+    let bytes = fs::read(path).map_err(|error| format!("{path}: {error}"))?;
+    read_las_survey(&String::from_utf8_lossy(&bytes)).map_err(|error| format!("{path}: {error}"))
+}
+
+/// The row of the `index`th record in errors, the header is row 1 as in a spreadsheet
+fn row_number(index: usize) -> usize {
+    index + 2
+}
+
+/// Open a file for reading, `-` reads from stdin
+fn open_input(path: &str) -> Box<dyn Read> {
+    match path {
+        "-" => Box::new(io::stdin().lock()),
+        path => {
+            Box::new(BufReader::new(File::open(path).unwrap_or_else(|error| {
+                exit_with_error(format!("{path}: {error}"))
+            })))
+        }
+    }
+}
+
+impl CsvInput {
+    /// Fill the unset options from `fallback`, column mappings of `self` take precedence
+    pub fn or(mut self, fallback: Self) -> Self {
+        self.delimiter = self.delimiter.or(fallback.delimiter);
+        self.decimal_comma = self.decimal_comma.or(fallback.decimal_comma);
+        self.columns.extend(fallback.columns);
+        self.length_unit = self.length_unit.or(fallback.length_unit);
+        self
+    }
+
+    /// The field delimiter, a comma unless set. A decimal comma needs another delimiter.
+    fn delimiter(&self) -> Result<u8, String> {
+        let delimiter = self.delimiter.unwrap_or(b',');
+        match self.decimal_comma == Some(true) && delimiter == b',' {
+            true => Err("--decimal-comma needs a delimiter other than a comma".to_string()),
+            false => Ok(delimiter),
+        }
+    }
+
+    /// Returns the renamed headers and an iterator over the records with decimal commas replaced
+    /// and lengths converted to metres
+    pub fn records(&self, path: &str) -> (StringRecord, impl Iterator<Item = StringRecord>) {
+        let (headers, rows): (StringRecord, Box<dyn Iterator<Item = StringRecord>>) =
+            match split_sheet(path) {
+                Some((workbook, sheet)) => read_sheet(workbook, sheet),
+                None => {
+                    let delimiter = self
+                        .delimiter()
+                        .unwrap_or_else(|error| exit_with_error(error));
+                    let mut rdr = csv::ReaderBuilder::new()
+                        .delimiter(delimiter)
+                        .from_reader(open_input(path));
+                    let headers = rdr
+                        .headers()
+                        .unwrap_or_else(|error| exit_with_error(format!("{path}: {error}")))
+                        .clone();
+                    let path = path.to_string();
+                    let records = rdr.into_records().enumerate().map(move |(index, record)| {
+                        record.unwrap_or_else(|error| {
+                            exit_with_error(format!("{path}: row {}: {error}", row_number(index)))
+                        })
+                    });
+                    (headers, Box::new(records))
+                }
+            };
+        let headers = headers
+            .iter()
+            .map(|header| {
+                self.columns
+                    .iter()
+                    .find(|(from, _)| from == header)
+                    .map_or(header, |(_, to)| to.as_str())
+            })
+            .collect::<StringRecord>();
+
+        let decimal_comma = headers
+            .iter()
+            .map(|header| self.decimal_comma == Some(true) && NUMERIC_COLUMNS.contains(&header))
+            .collect::<Vec<bool>>();
+        let scale = self.length_unit.unwrap_or_default().scale();
+        let length = headers
+            .iter()
+            .map(|header| scale != 1.0 && LENGTH_COLUMNS.contains(&header))
+            .collect::<Vec<bool>>();
+        let records = rows.map(move |record| {
+            if !decimal_comma.contains(&true) && !length.contains(&true) {
+                return record;
+            }
+            record
+                .iter()
+                .zip(decimal_comma.iter().zip(&length))
+                .map(|(field, (decimal_comma, length))| {
+                    let field = match decimal_comma {
+                        true => field.replace(',', "."),
+                        false => field.to_string(),
+                    };
+                    match field.trim().parse::<f64>() {
+                        Ok(value) if *length => (value * scale).to_string(),
+                        _ => field,
+                    }
+                })
+                .collect()
+        });
+        (headers, records)
+    }
+
+    pub fn read<T: DeserializeOwned>(&self, path: &str) -> Vec<T> {
+        let (headers, records) = self.records(path);
+        records
+            .enumerate()
+            .map(|(index, record)| {
+                record.deserialize(Some(&headers)).unwrap_or_else(|error| {
+                    exit_with_error(format!("{path}: row {}: {error}", row_number(index)))
+                })
+            })
+            .collect()
+    }
+
+    /// Read the measurements one row at a time and keep the columns geocalc does not use.
+    /// Returns the names of the extra columns and an iterator over the measurements.
+    pub fn read_measurements(
+        &self,
+        path: &str,
+    ) -> (Vec<String>, impl Iterator<Item = RawMeasurement>) {
+        let (headers, records) = self.records(path);
+        let extra_columns = headers
+            .iter()
+            .filter(|header| !MEASUREMENT_COLUMNS.contains(header))
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        let columns = extra_columns.clone();
+        let path = path.to_string();
+        let measurements = records.enumerate().map(move |(index, record)| {
+            let mut measurement: RawMeasurement =
+                record.deserialize(Some(&headers)).unwrap_or_else(|error| {
+                    exit_with_error(format!("{path}: row {}: {error}", row_number(index)))
+                });
+            measurement.extra = headers
+                .iter()
+                .zip(record.iter())
+                .filter(|(header, _)| columns.iter().any(|column| column == header))
+                .map(|(header, value)| (header.to_string(), value.to_string()))
+                .collect();
+            measurement
+        });
+        (extra_columns, measurements)
+    }
+
+    /// Read an interval table, rows without a hole id are assigned to `default_hole_id`
+    pub fn read_intervals(&self, path: &str, default_hole_id: &str) -> Vec<Interval> {
+        let (headers, records) = self.records(path);
+        records
+            .enumerate()
+            .map(|(index, record)| {
+                let row: IntervalRow = record.deserialize(Some(&headers)).unwrap_or_else(|error| {
+                    exit_with_error(format!("{path}: row {}: {error}", row_number(index)))
+                });
+                Interval {
+                    hole_id: row.hole_id.unwrap_or_else(|| default_hole_id.to_string()),
+                    from: row.from,
+                    to: row.to,
+                    values: headers
+                        .iter()
+                        .zip(record.iter())
+                        .filter(|(header, _)| !INTERVAL_COLUMNS.contains(header))
+                        .map(|(header, value)| (header.to_string(), value.to_string()))
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `text` to a file in the temporary directory and return its path
+    fn write_input(name: &str, text: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("geocalc-input-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn records(input: &CsvInput, name: &str, text: &str) -> (Vec<String>, Vec<Vec<String>>) {
+        let (headers, records) = input.records(&write_input(name, text));
+        (
+            headers.iter().map(String::from).collect(),
+            records
+                .map(|record| record.iter().map(String::from).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn csv_input_renamed_header() {
+        let input = CsvInput {
+            columns: vec![
+                ("HOLEID".to_string(), "hole_id".to_string()),
+                ("DEPTH".to_string(), "depth".to_string()),
+            ],
+            ..Default::default()
+        };
+        let (headers, rows) = records(&input, "renamed.csv", "HOLEID,DEPTH,Alpha\nDH1,12.5,40\n");
+        assert_eq!(headers, ["hole_id", "depth", "Alpha"]);
+        assert_eq!(rows, [["DH1", "12.5", "40"]]);
+    }
+
+    #[test]
+    fn csv_input_decimal_comma_in_numeric_columns() {
+        let input = CsvInput {
+            delimiter: Some(b';'),
+            decimal_comma: Some(true),
+            ..Default::default()
+        };
+        let (_, rows) = records(
+            &input,
+            "decimal_comma.csv",
+            "depth;alpha;beta;comment\n12,5;40;200,5;broken, sheared 1,5 m\n",
+        );
+        assert_eq!(rows, [["12.5", "40", "200.5", "broken, sheared 1,5 m"]]);
+    }
+
+    #[test]
+    fn csv_input_feet_in_length_columns() {
+        let input = CsvInput {
+            length_unit: Some(LengthUnit::Feet),
+            ..Default::default()
+        };
+        let (_, rows) = records(
+            &input,
+            "feet.csv",
+            "depth,alpha,beta,comment\n100,40,200,10\n",
+        );
+        let depth = rows[0][0].parse::<f64>().unwrap();
+        assert!((depth - 30.48).abs() < 1e-9);
+        assert_eq!(rows[0][1..], ["40", "200", "10"]);
+    }
+
+    #[test]
+    fn csv_input_decimal_comma_needs_another_delimiter() {
+        let input = CsvInput {
+            decimal_comma: Some(true),
+            ..Default::default()
+        };
+        assert!(input.delimiter().is_err());
+        assert!(CsvInput {
+            delimiter: Some(b','),
+            ..input.clone()
+        }
+        .delimiter()
+        .is_err());
+        assert_eq!(
+            CsvInput {
+                delimiter: Some(b';'),
+                ..input.clone()
+            }
+            .delimiter(),
+            Ok(b';')
+        );
+        assert_eq!(CsvInput::default().delimiter(), Ok(b','));
+    }
+
+    #[test]
+    fn delimiter_and_column_arguments() {
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert_eq!(parse_delimiter("\\t"), Ok(b'\t'));
+        assert_eq!(parse_delimiter("semicolon"), Ok(b';'));
+        assert_eq!(parse_delimiter("|"), Ok(b'|'));
+        assert!(parse_delimiter("||").is_err());
+        assert!(parse_delimiter("°").is_err());
+
+        assert_eq!(
+            parse_column("HOLEID=hole_id"),
+            Ok(("HOLEID".to_string(), "hole_id".to_string()))
+        );
+        assert!(parse_column("HOLEID").is_err());
+        assert!(parse_column("=hole_id").is_err());
+        assert!(parse_column("HOLEID=").is_err());
+    }
+}
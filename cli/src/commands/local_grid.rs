@@ -0,0 +1,159 @@
+use clap::{Args, ValueEnum};
+use geocalc::{ControlPoint, GridTransform, GridTransformKind, Residual};
+use serde::Deserialize;
+
+use super::input::CsvInput;
+
+/// How the local grid is fitted to the control points
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridKind {
+    /// Rotation, uniform scale and translation, needs at least 2 control points
+    #[default]
+    Similarity,
+    /// Rotation, scale in two directions, shear and translation, needs at least 3 control points
+    Affine,
+}
+
+impl From<GridKind> for GridTransformKind {
+    fn from(kind: GridKind) -> Self {
+        match kind {
+            GridKind::Similarity => Self::Similarity,
+            GridKind::Affine => Self::Affine,
+        }
+    }
+}
+
+/// A local mine grid, fitted to control points or given by its rotation, scale and origin.
+/// Unset values fall back to the project configuration.
+#[derive(Args, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LocalGrid {
+    /// Path to csv file containing control points known in the local grid and the UTM grid
+    /// Expected format:
+    /// [name,]local_x,local_y,x,y
+    #[arg(long = "grid-control")]
+    pub control: Option<String>,
+
+    /// How the local grid is fitted to the control points [default: similarity]
+    #[arg(long = "grid-kind", value_enum)]
+    pub kind: Option<GridKind>,
+
+    /// The UTM grid bearing of local grid north in degrees, instead of control points
+    #[arg(
+        long = "grid-rotation",
+        allow_hyphen_values = true,
+        conflicts_with = "control"
+    )]
+    pub rotation: Option<f64>,
+
+    /// The UTM length of a local unit length [default: 1]
+    #[arg(long = "grid-scale")]
+    pub scale: Option<f64>,
+
+    /// A point in local coordinates, e.g. 10000,5000, that is at --grid-map-origin [default: 0,0]
+    #[arg(
+        long = "grid-local-origin",
+        value_delimiter = ',',
+        num_args = 2,
+        allow_hyphen_values = true
+    )]
+    pub local_origin: Option<Vec<f64>>,
+
+    /// The UTM coordinates of --grid-local-origin [default: 0,0]
+    #[arg(
+        long = "grid-map-origin",
+        value_delimiter = ',',
+        num_args = 2,
+        allow_hyphen_values = true
+    )]
+    pub map_origin: Option<Vec<f64>>,
+}
+
+impl LocalGrid {
+    /// Fill the unset values from `fallback`, control points and a rotation replace each other
+    pub fn or(self, fallback: Self) -> Self {
+        let (control, rotation) = match self.control.is_some() || self.rotation.is_some() {
+            true => (self.control, self.rotation),
+            false => (fallback.control, fallback.rotation),
+        };
+        Self {
+            control,
+            kind: self.kind.or(fallback.kind),
+            rotation,
+            scale: self.scale.or(fallback.scale),
+            local_origin: self.local_origin.or(fallback.local_origin),
+            map_origin: self.map_origin.or(fallback.map_origin),
+        }
+    }
+
+    /// The transform of the local grid, `None` when no local grid is set.
+    /// The residuals of the control points are printed unless `quiet`.
+    pub fn transform(
+        &self,
+        input: &CsvInput,
+        quiet: bool,
+    ) -> Result<Option<GridTransform>, String> {
+        match (&self.control, self.rotation) {
+            (Some(_), Some(_)) => Err(
+                "The local grid has both control points and a rotation, use only one".to_string(),
+            ),
+            (Some(path), None) => {
+                let points = input.read::<ControlPoint>(path);
+                let (transform, residuals) =
+                    GridTransform::fit(&points, self.kind.unwrap_or_default().into())
+                        .map_err(|error| format!("{path}: {error}"))?;
+                if !quiet {
+                    print_residuals(&transform, &residuals);
+                }
+                Ok(Some(transform))
+            }
+            (None, Some(rotation)) => {
+                let point = |origin: &Option<Vec<f64>>| match origin.as_deref() {
+                    None => Ok((0.0, 0.0)),
+                    Some(&[x, y]) => Ok((x, y)),
+                    Some(_) => Err("A local grid origin needs an x and a y".to_string()),
+                };
+                GridTransform::similarity(
+                    rotation,
+                    self.scale.unwrap_or(1.0),
+                    point(&self.local_origin)?,
+                    point(&self.map_origin)?,
+                )
+                .map(Some)
+            }
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+fn print_residuals(transform: &GridTransform, residuals: &[Residual]) {
+    eprintln!(
+        "Local grid: rotation {:.6}°, scale {:.8}",
+        transform.rotation(),
+        transform.scale()
+    );
+    eprintln!(
+        "{:<12} {:>10} {:>10} {:>10}",
+        "control", "dx", "dy", "distance"
+    );
+    for (index, residual) in residuals.iter().enumerate() {
+        eprintln!(
+            "{:<12} {:>10.4} {:>10.4} {:>10.4}",
+            residual
+                .name
+                .clone()
+                .unwrap_or_else(|| (index + 1).to_string()),
+            residual.dx,
+            residual.dy,
+            residual.distance
+        );
+    }
+    let rms = (residuals
+        .iter()
+        .map(|residual| residual.distance.powi(2))
+        .sum::<f64>()
+        / residuals.len() as f64)
+        .sqrt();
+    eprintln!("{:<12} {:>32.4}", "rms", rms);
+}
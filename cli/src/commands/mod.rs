@@ -1,5 +1,24 @@
 mod borehole;
+mod config;
+mod conventions;
+mod coords;
+mod grid;
+mod input;
+mod local_grid;
 mod orient_one;
+mod output;
+mod survey_merge;
+mod survey_qa;
+mod survey_smoothing;
+mod uncertainty;
 
 pub use borehole::{borehole, Borehole};
+pub use config::{init, Init};
+pub use coords::{coords, Coords};
+pub use grid::{grid, Grid};
 pub use orient_one::{orient_one, OrientOne};
+
+fn exit_with_error(error: String) -> ! {
+    eprintln!("Error: {error}");
+    std::process::exit(1);
+}
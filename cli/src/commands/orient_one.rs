@@ -1,5 +1,10 @@
 use clap::Args;
-use geocalc::{BHOrientationLine, Plane};
+use geocalc::{
+    BHOrientationLine, BetaConvention as GCBetaConvention,
+    InclinationConvention as GCInclinationConvention, Plane,
+};
+
+use super::conventions::{BetaConvention, InclinationConvention};
 
 // #[derive(ValueEnum, Clone)]
 // enum Structure {
@@ -12,31 +17,137 @@ pub struct OrientOne {
     #[arg(long)]
     bearing: f64,
 
-    #[arg(long)]
+    #[arg(long, allow_hyphen_values = true)]
     inclination: f64,
 
+    /// The convention used for the inclination, the same default as the borehole command [default: negative-down]
+    #[arg(long, value_enum)]
+    inclination_convention: Option<InclinationConvention>,
+
     #[arg(long)]
     alpha: f64,
 
     #[arg(long)]
     beta: f64,
 
+    #[command(flatten)]
+    beta_convention: BetaConvention,
+
     #[arg(long)]
     bottom: bool,
+
+    /// The orientation line beta was measured from: top, bottom or an offset angle from the top
+    #[arg(long, conflicts_with = "bottom")]
+    orientation_line: Option<BHOrientationLine>,
 }
 
-pub fn orient_one(cmd: OrientOne) {
-    let plane = Plane::alpha_beta(
-        cmd.bearing,
-        cmd.inclination * -1.0,
-        cmd.alpha,
-        cmd.beta,
-        if cmd.bottom {
-            BHOrientationLine::Bottom
-        } else {
-            BHOrientationLine::Top
-        },
-    );
+impl OrientOne {
+    fn plane(&self) -> Plane {
+        let inclination_convention: GCInclinationConvention =
+            self.inclination_convention.unwrap_or_default().into();
+        let beta_convention: GCBetaConvention = self.beta_convention.into();
+        Plane::alpha_beta(
+            self.bearing,
+            inclination_convention.to_negative_down(self.inclination),
+            self.alpha,
+            beta_convention.to_internal(self.beta),
+            match self.orientation_line {
+                Some(orientation_line) => orientation_line,
+                None if self.bottom => BHOrientationLine::Bottom,
+                None => BHOrientationLine::Top,
+            },
+        )
+    }
+}
 
+pub fn orient_one(cmd: OrientOne) {
+    let plane = cmd.plane();
     println!("{plane:#?}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Borehole;
+    use clap::Parser;
+    use geocalc::{BHOrientation, Borehole as GCBorehole, RawMeasurement};
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        orient_one: OrientOne,
+    }
+
+    #[derive(Parser)]
+    struct BoreholeCli {
+        #[command(flatten)]
+        borehole: Borehole,
+    }
+
+    fn plane(args: &str) -> Plane {
+        Cli::try_parse_from(format!("orient-one {args}").split_whitespace())
+            .unwrap()
+            .orient_one
+            .plane()
+    }
+
+    /// The plane the borehole command orients from a survey station read with the convention of `args`
+    fn borehole_plane(args: &str, inclination: f64) -> Plane {
+        let borehole = BoreholeCli::try_parse_from(format!("borehole {args}").split_whitespace())
+            .unwrap()
+            .borehole;
+        let measurement = RawMeasurement {
+            hole_id: None,
+            depth: 10.0,
+            alpha: 40.0,
+            beta: Some(200.0),
+            orientation_line: None,
+            extra: Default::default(),
+        };
+        let station = BHOrientation {
+            hole_id: None,
+            survey_type: None,
+            depth: 0.0,
+            bearing: 120.0,
+            inclination,
+        };
+        GCBorehole::new(
+            BHOrientationLine::Top,
+            borehole.inclination_convention.unwrap_or_default().into(),
+            borehole.beta_convention.into(),
+            vec![measurement],
+            vec![station],
+            vec![],
+        )
+        .oriented_measurements
+        .remove(0)
+        .plane
+        .unwrap()
+    }
+
+    #[test]
+    fn inclination_convention_default_is_shared() {
+        for (convention, inclination) in [
+            ("", -55.0),
+            ("--inclination-convention positive-down", 55.0),
+            ("--inclination-convention from-vertical", 35.0),
+        ] {
+            let orient_one = plane(&format!(
+                "--bearing 120 --inclination {inclination} --alpha 40 --beta 200 {convention}"
+            ));
+            let borehole = borehole_plane(convention, inclination);
+            assert!((orient_one.dip - borehole.dip).abs() < 1e-9, "{convention}");
+            assert!(
+                (orient_one.dip_direction - borehole.dip_direction).abs() < 1e-9,
+                "{convention}"
+            );
+        }
+        // Both read a bare inclination as negative down
+        let default = plane("--bearing 120 --inclination -55 --alpha 40 --beta 200");
+        let baseline = Plane::alpha_beta(120.0, -55.0, 40.0, 200.0, BHOrientationLine::Top);
+        assert_eq!(
+            (default.dip, default.dip_direction),
+            (baseline.dip, baseline.dip_direction)
+        );
+    }
+}
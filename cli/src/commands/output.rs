@@ -0,0 +1,268 @@
+use clap::ValueEnum;
+use geocalc::{geodetic_from_utm, OrientedMeasurement, UtmZone};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use super::survey_qa::join_flags;
+
+/// The format the oriented measurements are written in
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// One row per measurement with flat columns
+    #[default]
+    Csv,
+    /// A single JSON array of measurements
+    Json,
+    /// One JSON measurement per line
+    Ndjson,
+    /// A FeatureCollection of WGS84 points. Measurements have no geometry when their hole has no collar
+    /// or the UTM zone of the collars is not known.
+    Geojson,
+}
+
+/// A measurement with the hole it belongs to
+#[derive(Serialize)]
+struct Record<'a> {
+    hole_id: &'a str,
+    #[serde(flatten)]
+    measurement: &'a OrientedMeasurement,
+}
+
+#[derive(Serialize)]
+struct Point {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    coordinates: [f64; 3],
+}
+
+#[derive(Serialize)]
+struct Feature<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    geometry: Option<Point>,
+    properties: Record<'a>,
+}
+
+/// Open a file for writing, `-` writes to stdout
+pub fn open_output(path: &str) -> Box<dyn Write> {
+    match path {
+        "-" => Box::new(io::stdout().lock()),
+        path => Box::new(BufWriter::new(File::create(path).unwrap())),
+    }
+}
+
+/// Writes oriented measurements one at a time so that tables of any size can be streamed
+pub struct MeasurementWriter<W: Write> {
+    format: Format,
+    sink: Sink<W>,
+    extra_columns: Vec<String>,
+    /// The zone of the collar coordinates, GeoJSON points are written in longitude and latitude
+    utm_zone: Option<UtmZone>,
+    written: usize,
+}
+
+enum Sink<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Json(W),
+}
+
+impl<W: Write> MeasurementWriter<W> {
+    /// Start writing, `extra_columns` are the names of the input columns that are passed through to the CSV.
+    /// GeoJSON locations are converted from `utm_zone` and left out when it is `None`.
+    pub fn new(
+        format: Format,
+        writer: W,
+        extra_columns: Vec<String>,
+        utm_zone: Option<UtmZone>,
+    ) -> Self {
+        let sink = match format {
+            Format::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                #[rustfmt::skip]
+                let columns = ["hole_id", "depth", "alpha", "beta", "orientation_line", "north", "bearing", "inclination", "x", "y", "z", "strike", "dip", "dip_direction", "pole.trend", "pole.plunge", "confidence", "cone.trend", "cone.plunge", "cone.half_angle", "pole.cone_95", "dominant_error", "sensitivity.bearing", "sensitivity.inclination", "sensitivity.alpha", "sensitivity.beta", "survey_flags"];
+                writer
+                    .write_record(
+                        columns
+                            .iter()
+                            .copied()
+                            .chain(extra_columns.iter().map(String::as_str)),
+                    )
+                    .unwrap();
+                Sink::Csv(Box::new(writer))
+            }
+            Format::Json => {
+                let mut writer = writer;
+                write!(writer, "[").unwrap();
+                Sink::Json(writer)
+            }
+            Format::Ndjson => Sink::Json(writer),
+            Format::Geojson => {
+                let mut writer = writer;
+                write!(writer, r#"{{"type":"FeatureCollection","features":["#).unwrap();
+                Sink::Json(writer)
+            }
+        };
+        Self {
+            format,
+            sink,
+            extra_columns,
+            utm_zone,
+            written: 0,
+        }
+    }
+
+    pub fn write(&mut self, hole_id: &str, measurement: &OrientedMeasurement) {
+        let record = Record {
+            hole_id,
+            measurement,
+        };
+        let first = self.written == 0;
+        self.written += 1;
+        match &mut self.sink {
+            Sink::Csv(writer) => write_csv_record(writer, &record, &self.extra_columns),
+            Sink::Json(writer) => {
+                // Arrays are written with one element per line
+                match self.format {
+                    Format::Json | Format::Geojson if first => writeln!(writer).unwrap(),
+                    Format::Json | Format::Geojson => writeln!(writer, ",").unwrap(),
+                    _ => (),
+                }
+                match self.format {
+                    Format::Geojson => serde_json::to_writer(
+                        &mut *writer,
+                        &Feature {
+                            kind: "Feature",
+                            geometry: measurement.location.zip(self.utm_zone).map(
+                                |(location, zone)| {
+                                    let (latitude, longitude) =
+                                        geodetic_from_utm(location.x, location.y, zone);
+                                    Point {
+                                        kind: "Point",
+                                        coordinates: [longitude, latitude, location.z],
+                                    }
+                                },
+                            ),
+                            properties: record,
+                        },
+                    ),
+                    _ => serde_json::to_writer(&mut *writer, &record),
+                }
+                .unwrap();
+                if let Format::Ndjson = self.format {
+                    writeln!(writer).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Close any open arrays and flush the output.
+    pub fn finish(self) {
+        match self.sink {
+            Sink::Csv(mut writer) => writer.flush().unwrap(),
+            Sink::Json(mut writer) => {
+                match self.format {
+                    Format::Json => writeln!(writer, "\n]").unwrap(),
+                    Format::Geojson => writeln!(writer, "\n]}}").unwrap(),
+                    _ => (),
+                }
+                writer.flush().unwrap();
+            }
+        }
+    }
+}
+
+fn write_csv_record<W: Write>(
+    writer: &mut csv::Writer<W>,
+    record: &Record,
+    extra_columns: &[String],
+) {
+    let Record {
+        hole_id,
+        measurement,
+    } = record;
+    let raw = &measurement.raw;
+    let input_fields = [
+        raw.alpha.to_string(),
+        raw.beta.map(|beta| beta.to_string()).unwrap_or_default(),
+        raw.orientation_line
+            .map(|line| line.to_string())
+            .unwrap_or_default(),
+        measurement.north.to_string(),
+        measurement.bearing.to_string(),
+        measurement.inclination.to_string(),
+    ];
+    let location_fields = match measurement.location {
+        Some(location) => [
+            location.x.to_string(),
+            location.y.to_string(),
+            location.z.to_string(),
+        ],
+        // The location is only known when the collar of the hole was supplied
+        None => Default::default(),
+    };
+    let plane_fields = match measurement.plane {
+        Some(plane) => [
+            plane.strike.to_string(),
+            plane.dip.to_string(),
+            plane.dip_direction.to_string(),
+            plane.pole.trend.to_string(),
+            plane.pole.plunge.to_string(),
+        ],
+        // Unoriented measurements are written with empty orientation fields
+        None => Default::default(),
+    };
+    let confidence = match measurement.confidence {
+        Some(confidence) => confidence.to_string(),
+        None => String::new(),
+    };
+    // Measurements that could not be oriented get the cone of possible poles instead
+    let cone_fields = match measurement.cone {
+        Some(cone) => [
+            cone.axis.trend.to_string(),
+            cone.axis.plunge.to_string(),
+            cone.half_angle.to_string(),
+        ],
+        None => Default::default(),
+    };
+    let cone_95 = match measurement.uncertainty {
+        Some(uncertainty) => uncertainty.cone_95.to_string(),
+        None => String::new(),
+    };
+    let survey_flags = join_flags(&measurement.survey_flags);
+    // Degrees the pole rotates for a degree of error on each input, only from error propagation
+    let sensitivity_fields = match measurement.sensitivities {
+        Some(sensitivities) => [
+            sensitivities.dominant.to_string(),
+            sensitivities.bearing.to_string(),
+            sensitivities.inclination.to_string(),
+            sensitivities.alpha.to_string(),
+            sensitivities.beta.to_string(),
+        ],
+        None => Default::default(),
+    };
+
+    writer.write_field(hole_id).unwrap();
+    writer.write_field(measurement.depth.to_string()).unwrap();
+    let extra_fields = extra_columns
+        .iter()
+        .map(|column| raw.extra.get(column).cloned().unwrap_or_default());
+    writer
+        .write_record(
+            input_fields
+                .iter()
+                .chain(location_fields.iter())
+                .chain(plane_fields.iter())
+                .chain([&confidence])
+                .chain(cone_fields.iter())
+                .chain([&cone_95])
+                .chain(sensitivity_fields.iter())
+                .chain([&survey_flags])
+                .cloned()
+                .chain(extra_fields),
+        )
+        .unwrap();
+}
@@ -0,0 +1,54 @@
+use geocalc::SurveyDisagreement;
+use std::{collections::BTreeMap, io::Write};
+
+fn survey_name(survey_type: &Option<String>) -> &str {
+    survey_type.as_deref().unwrap_or("untyped")
+}
+
+/// Print how much each overridden survey of a hole differs from the surveys chosen over it
+pub fn print_disagreements(hole_id: &str, disagreements: &[SurveyDisagreement]) {
+    let mut angles: BTreeMap<(&str, &str), Vec<f64>> = BTreeMap::new();
+    for disagreement in disagreements {
+        angles
+            .entry((
+                survey_name(&disagreement.survey_type),
+                survey_name(&disagreement.chosen_type),
+            ))
+            .or_default()
+            .push(disagreement.angle);
+    }
+    for ((survey_type, chosen_type), angles) in angles {
+        eprintln!(
+            "{hole_id}: the {survey_type} survey differs from the {chosen_type} survey by {:.2}° on average and {:.2}° at most over {} stations",
+            angles.iter().sum::<f64>() / angles.len() as f64,
+            angles.iter().copied().fold(0.0, f64::max),
+            angles.len()
+        );
+    }
+}
+
+/// Write every overridden station of every hole as csv
+pub fn write_disagreements<'a, W: Write>(
+    writer: W,
+    holes: impl Iterator<Item = (&'a String, &'a [SurveyDisagreement])>,
+) {
+    let mut writer = csv::Writer::from_writer(writer);
+    #[rustfmt::skip]
+    writer.write_record(["hole_id", "depth", "survey_type", "chosen_type", "bearing_difference", "inclination_difference", "angle"]).unwrap();
+    for (hole_id, disagreements) in holes {
+        for disagreement in disagreements {
+            writer
+                .write_record([
+                    hole_id.clone(),
+                    disagreement.depth.to_string(),
+                    survey_name(&disagreement.survey_type).to_string(),
+                    survey_name(&disagreement.chosen_type).to_string(),
+                    disagreement.bearing_difference.to_string(),
+                    disagreement.inclination_difference.to_string(),
+                    disagreement.angle.to_string(),
+                ])
+                .unwrap();
+        }
+    }
+    writer.flush().unwrap();
+}
@@ -0,0 +1,103 @@
+use clap::Args;
+use geocalc::{StationQa, SurveyFlag, SurveyQaLimits};
+use serde::Deserialize;
+use std::io::Write;
+
+/// Unset limits fall back to the project configuration and then the default
+#[derive(Args, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SurveyQa {
+    /// Check the surveys for doglegs, jumps and outliers, e.g. magnetic readings disturbed by massive sulphides.
+    /// Flagged stations are reported and measurements oriented with them are flagged in the output.
+    #[arg(long = "survey-qa")]
+    pub enabled: bool,
+
+    /// The largest dogleg severity in degrees per 30 m [default: 3]
+    #[arg(long = "max-dogleg-severity")]
+    pub dogleg_severity: Option<f64>,
+
+    /// The largest bearing change between stations in degrees [default: 5]
+    #[arg(long = "max-bearing-jump")]
+    pub bearing_jump: Option<f64>,
+
+    /// The largest inclination change between stations in degrees [default: 3]
+    #[arg(long = "max-inclination-jump")]
+    pub inclination_jump: Option<f64>,
+
+    /// The largest angle in degrees between a station and the trend of its neighbours [default: 2]
+    #[arg(long = "max-trend-deviation")]
+    pub trend_deviation: Option<f64>,
+
+    /// Path to where the checks of every survey station are written as csv, implies --survey-qa
+    #[arg(long = "survey-qa-report")]
+    pub report: Option<String>,
+}
+
+impl SurveyQa {
+    /// Fill the unset values from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            enabled: self.enabled || fallback.enabled,
+            dogleg_severity: self.dogleg_severity.or(fallback.dogleg_severity),
+            bearing_jump: self.bearing_jump.or(fallback.bearing_jump),
+            inclination_jump: self.inclination_jump.or(fallback.inclination_jump),
+            trend_deviation: self.trend_deviation.or(fallback.trend_deviation),
+            report: self.report.or(fallback.report),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled || self.report.is_some()
+    }
+
+    pub fn limits(&self) -> SurveyQaLimits {
+        let default = SurveyQaLimits::default();
+        SurveyQaLimits {
+            dogleg_severity: self.dogleg_severity.unwrap_or(default.dogleg_severity),
+            bearing_jump: self.bearing_jump.unwrap_or(default.bearing_jump),
+            inclination_jump: self.inclination_jump.unwrap_or(default.inclination_jump),
+            trend_deviation: self.trend_deviation.unwrap_or(default.trend_deviation),
+        }
+    }
+}
+
+fn optional(value: Option<f64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Write the checks of every station of every hole as csv
+pub fn write_report<'a, W: Write>(
+    writer: W,
+    holes: impl Iterator<Item = (&'a String, &'a [StationQa])>,
+) {
+    let mut writer = csv::Writer::from_writer(writer);
+    #[rustfmt::skip]
+    writer.write_record(["hole_id", "depth", "bearing", "inclination", "dogleg_severity", "bearing_change", "inclination_change", "trend_deviation", "flags"]).unwrap();
+    for (hole_id, stations) in holes {
+        for station in stations {
+            writer
+                .write_record([
+                    hole_id.clone(),
+                    station.depth.to_string(),
+                    station.bearing.to_string(),
+                    station.inclination.to_string(),
+                    optional(station.dogleg_severity),
+                    optional(station.bearing_change),
+                    optional(station.inclination_change),
+                    optional(station.trend_deviation),
+                    join_flags(&station.flags),
+                ])
+                .unwrap();
+        }
+    }
+    writer.flush().unwrap();
+}
+
+/// Flags separated by semicolons
+pub fn join_flags(flags: &[SurveyFlag]) -> String {
+    flags
+        .iter()
+        .map(|flag| flag.to_string())
+        .collect::<Vec<String>>()
+        .join(";")
+}
@@ -0,0 +1,144 @@
+use clap::Args;
+use geocalc::{Borehole, SurveySmoothing as GCSurveySmoothing};
+use serde::Deserialize;
+use std::io::Write;
+
+/// Unset values fall back to the project configuration and then the default
+#[derive(Args, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SurveySmoothing {
+    /// Smooth the surveys with a median filter on the direction of the hole before the measurements are
+    /// oriented, and remove stations that are far from the median
+    #[arg(id = "smooth_survey", long = "smooth-survey")]
+    pub enabled: bool,
+
+    /// The number of stations the median is taken over [default: 5]
+    #[arg(
+        id = "smoothing_window",
+        long = "smoothing-window",
+        value_name = "WINDOW"
+    )]
+    pub window: Option<usize>,
+
+    /// Stations further than this angle in degrees from the median are removed [default: 3]
+    #[arg(long = "rejection-angle", conflicts_with = "keep_outliers")]
+    pub rejection_angle: Option<f64>,
+
+    /// Replace stations that are far from the median by the median instead of removing them
+    #[arg(long = "keep-outliers")]
+    pub keep_outliers: bool,
+
+    /// Path to where the smoothed surveys are written as csv, implies --smooth-survey.
+    /// Bearings refer to true north and inclinations use the convention of each hole.
+    #[arg(
+        id = "smoothed_survey",
+        long = "smoothed-survey",
+        value_name = "OUTPUT"
+    )]
+    pub output: Option<String>,
+
+    /// Path to where what smoothing did to every survey station is written as csv, implies --smooth-survey.
+    /// Inclinations use the convention of each hole, as in --smoothed-survey.
+    #[arg(
+        id = "survey_smoothing_report",
+        long = "survey-smoothing-report",
+        value_name = "REPORT"
+    )]
+    pub report: Option<String>,
+}
+
+impl SurveySmoothing {
+    /// Fill the unset values from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            enabled: self.enabled || fallback.enabled,
+            window: self.window.or(fallback.window),
+            rejection_angle: self.rejection_angle.or(fallback.rejection_angle),
+            keep_outliers: self.keep_outliers || fallback.keep_outliers,
+            output: self.output.or(fallback.output),
+            report: self.report.or(fallback.report),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled || self.output.is_some() || self.report.is_some()
+    }
+
+    pub fn smoothing(&self) -> Result<GCSurveySmoothing, String> {
+        let default = GCSurveySmoothing::default();
+        let window = self.window.unwrap_or(default.window);
+        if window < 3 {
+            return Err(format!(
+                "The smoothing window must be at least 3 stations, found {window}"
+            ));
+        }
+        Ok(GCSurveySmoothing {
+            window,
+            rejection_angle: match self.keep_outliers {
+                true => None,
+                false => self.rejection_angle.or(default.rejection_angle),
+            },
+        })
+    }
+}
+
+/// Write the smoothed survey of every hole as csv, in the layout of the orientation input
+pub fn write_survey<'a, W: Write>(
+    writer: W,
+    holes: impl Iterator<Item = (&'a String, &'a Borehole)>,
+) {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer
+        .write_record(["hole_id", "depth", "bearing", "inclination"])
+        .unwrap();
+    for (hole_id, borehole) in holes {
+        for station in &borehole.hole_orientation {
+            writer
+                .write_record([
+                    hole_id.clone(),
+                    station.depth.to_string(),
+                    station.bearing.to_string(),
+                    borehole
+                        .inclination_convention
+                        .from_negative_down(station.inclination)
+                        .to_string(),
+                ])
+                .unwrap();
+        }
+    }
+    writer.flush().unwrap();
+}
+
+/// Write what smoothing did to every station of every hole as csv, with inclinations in the convention
+/// of the hole
+pub fn write_report<'a, W: Write>(
+    writer: W,
+    holes: impl Iterator<Item = (&'a String, &'a Borehole)>,
+) {
+    let mut writer = csv::Writer::from_writer(writer);
+    #[rustfmt::skip]
+    writer.write_record(["hole_id", "depth", "bearing", "inclination", "smoothed_bearing", "smoothed_inclination", "change", "rejected"]).unwrap();
+    for (hole_id, borehole) in holes {
+        let inclination = |inclination| {
+            borehole
+                .inclination_convention
+                .from_negative_down(inclination)
+                .to_string()
+        };
+        for station in &borehole.smoothed_survey {
+            writer
+                .write_record([
+                    hole_id.clone(),
+                    station.depth.to_string(),
+                    station.bearing.to_string(),
+                    inclination(station.inclination),
+                    station.smoothed_bearing.to_string(),
+                    inclination(station.smoothed_inclination),
+                    station.change.to_string(),
+                    station.rejected.to_string(),
+                ])
+                .unwrap();
+        }
+    }
+    writer.flush().unwrap();
+}
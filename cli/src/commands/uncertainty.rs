@@ -0,0 +1,66 @@
+use clap::{Args, ValueEnum};
+use geocalc::MeasurementErrors;
+
+/// How the input errors are propagated to the oriented poles
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum Method {
+    /// Sample perturbed inputs, slow but makes no assumptions
+    #[default]
+    MonteCarlo,
+    /// First order error propagation, fast and reports which input dominates the error
+    Analytical,
+}
+
+#[derive(Args)]
+pub struct Uncertainty {
+    /// How the input errors are propagated to the oriented poles
+    #[arg(long, value_enum, default_value_t)]
+    pub uncertainty_method: Method,
+
+    /// One standard deviation of the survey bearing error in degrees
+    #[arg(long, default_value_t = 0.0)]
+    pub bearing_error: f64,
+
+    /// One standard deviation of the survey inclination error in degrees
+    #[arg(long, default_value_t = 0.0)]
+    pub inclination_error: f64,
+
+    /// One standard deviation of the alpha reading error in degrees
+    #[arg(long, default_value_t = 0.0)]
+    pub alpha_error: f64,
+
+    /// One standard deviation of the beta reading error in degrees
+    #[arg(long, default_value_t = 0.0)]
+    pub beta_error: f64,
+
+    /// Number of Monte Carlo samples per measurement
+    #[arg(long, default_value_t = 1000)]
+    pub samples: usize,
+
+    /// Seed for the Monte Carlo sampling, the same seed always gives the same output
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+impl Uncertainty {
+    /// Uncertainty is only estimated when at least one error is given
+    pub fn is_enabled(&self) -> bool {
+        [
+            self.bearing_error,
+            self.inclination_error,
+            self.alpha_error,
+            self.beta_error,
+        ]
+        .iter()
+        .any(|error| *error > 0.0)
+    }
+
+    pub fn errors(&self) -> MeasurementErrors {
+        MeasurementErrors {
+            bearing: self.bearing_error,
+            inclination: self.inclination_error,
+            alpha: self.alpha_error,
+            beta: self.beta_error,
+        }
+    }
+}
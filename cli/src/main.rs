@@ -11,7 +11,13 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Borehole(commands::Borehole),
+    Borehole(Box<commands::Borehole>),
+    /// Convert coordinates between WGS84 latitude/longitude and UTM, e.g. collars from a handheld GPS
+    Coords(commands::Coords),
+    /// Convert coordinates and azimuths between a local mine grid and the UTM grid
+    Grid(commands::Grid),
+    /// Write a commented geocalc.toml configuration template
+    Init(commands::Init),
     OrientOne(commands::OrientOne),
 }
 
@@ -20,7 +26,16 @@ fn main() {
 
     match cli.command {
         Some(Commands::Borehole(borehole)) => {
-            commands::borehole(borehole);
+            commands::borehole(*borehole);
+        }
+        Some(Commands::Coords(coords)) => {
+            commands::coords(coords);
+        }
+        Some(Commands::Grid(grid)) => {
+            commands::grid(grid);
+        }
+        Some(Commands::Init(init)) => {
+            commands::init(init);
         }
         Some(Commands::OrientOne(orient_one)) => {
             commands::orient_one(orient_one);
@@ -1,86 +1,667 @@
 use na::{Matrix3, Vector3};
-use serde::Deserialize;
-use std::{
-    cmp::Ordering,
-    f64::consts::{FRAC_PI_2, PI},
-};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap, f64::consts::FRAC_PI_2, fmt, str::FromStr};
 
 use crate::{
-    structure::Plane,
-    utils::{dip_direction_from_strike, dip_from_plunge, strike_from_trend},
+    core_run::{run_at_depth, CoreRun, OrientationConfidence},
+    desurvey::{DesurveyedHole, Location},
+    project::{AzimuthDatum, Collar},
+    structure::{Lineation, Plane},
+    survey_merge::SurveyDisagreement,
+    survey_qa::{survey_qa, StationQa, SurveyFlag, SurveyQaLimits},
+    survey_smoothing::SmoothedStation,
+    uncertainty::{ErrorEllipse, MeasurementErrors, PoleUncertainty, Sensitivities},
+    unoriented::AlphaCone,
+    utils::{normalise_azimuth, vector::trend_and_plunge_from_vector},
     validation::error_if_out_of_range,
 };
 
-#[derive(Debug, Clone, Copy)]
+/// The line marked along the core by the orientation tool.
+///
+/// `Top` is the roof of the hole, i.e. the upper side of the core under gravity. This holds for up-holes
+/// drilled from underground as well: the top line of an up-hole faces back towards the collar side of the hole.
+/// In a vertical hole the roof is undefined, so the reference line is taken to face the recorded bearing in a
+/// down-hole and away from it in an up-hole. This is the limit of an inclined hole with the same bearing.
+///
+/// `Offset` is a line marked at a known angle (in degrees) from the top of the hole,
+/// measured clockwise looking down-hole. `Bottom` is the same as an offset of 180°.
+///
+/// Parses from `top`, `bottom` or an offset angle such as `90`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum BHOrientationLine {
+    #[default]
     Top,
     Bottom,
+    Offset(f64),
+}
+
+impl BHOrientationLine {
+    /// The angle (in degrees) of the orientation line from the top of the hole,
+    /// measured clockwise looking down-hole.
+    pub fn offset(&self) -> f64 {
+        match self {
+            Self::Top => 0.0,
+            Self::Bottom => 180.0,
+            Self::Offset(offset) => *offset,
+        }
+    }
+}
+
+impl FromStr for BHOrientationLine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "top" | "t" => Ok(Self::Top),
+            "bottom" | "b" => Ok(Self::Bottom),
+            other => other
+                .parse::<f64>()
+                .map_err(|_| {
+                    format!("Unknown orientation line {s}, expected top, bottom or an angle")
+                })
+                .and_then(|offset| error_if_out_of_range(&offset, 0.0, 360.0))
+                .map(Self::Offset),
+        }
+    }
+}
+
+impl fmt::Display for BHOrientationLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Top => write!(f, "top"),
+            Self::Bottom => write!(f, "bottom"),
+            Self::Offset(offset) => write!(f, "{offset}"),
+        }
+    }
+}
+
+impl From<BHOrientationLine> for String {
+    fn from(value: BHOrientationLine) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for BHOrientationLine {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// The direction beta is measured in, as seen when looking down the hole.
+/// Measuring counter-clockwise while looking up-hole is the same as `ClockwiseDownHole`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BetaDirection {
+    #[default]
+    ClockwiseDownHole,
+    CounterClockwiseDownHole,
+}
+
+/// The apex of the ellipse traced by the structure on the core wall that beta is measured to.
+/// The lower apex is the point furthest down the hole.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BetaApex {
+    #[default]
+    Lower,
+    Upper,
+}
+
+/// The convention used to measure beta on the core.
+/// The default is clockwise looking down-hole to the lower apex, which is the convention used by `Orient`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BetaConvention {
+    pub direction: BetaDirection,
+    pub apex: BetaApex,
+}
+
+impl BetaConvention {
+    pub fn new(direction: BetaDirection, apex: BetaApex) -> Self {
+        Self { direction, apex }
+    }
+
+    /// Converts a beta angle (in degrees) measured in this convention to clockwise looking down-hole
+    /// to the lower apex. The angle is still relative to the same orientation line.
+    pub fn to_internal(&self, beta: f64) -> f64 {
+        error_if_out_of_range(&beta, 0.0, 360.0).unwrap();
+
+        let beta = match self.direction {
+            BetaDirection::ClockwiseDownHole => beta,
+            BetaDirection::CounterClockwiseDownHole => 360.0 - beta,
+        };
+        let beta = match self.apex {
+            BetaApex::Lower => beta,
+            BetaApex::Upper => beta + 180.0,
+        };
+
+        normalise_azimuth(beta)
+    }
+}
+
+/// The convention used to record the inclination of a borehole survey.
+/// Inclinations are converted to `NegativeDown` before they are used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InclinationConvention {
+    /// Angle from the horizontal between -90° and 90°, negative when the hole points downward.
+    #[default]
+    NegativeDown,
+    /// Angle from the horizontal between -90° and 90°, positive when the hole points downward.
+    PositiveDown,
+    /// Angle from the vertical between 0° and 180°, where 0° points straight down.
+    /// Many survey tools report this as the zenith angle.
+    FromVertical,
 }
 
-impl Default for BHOrientationLine {
-    fn default() -> Self {
-        Self::Top
+impl InclinationConvention {
+    /// Converts an inclination (in degrees) recorded in this convention to the `NegativeDown` convention.
+    pub fn to_negative_down(&self, inclination: f64) -> f64 {
+        self.try_to_negative_down(inclination).unwrap()
+    }
+
+    /// Like `to_negative_down` but returns an error when the inclination is out of range for the convention.
+    pub fn try_to_negative_down(&self, inclination: f64) -> Result<f64, String> {
+        match self {
+            Self::NegativeDown => error_if_out_of_range(&inclination, -90.0, 90.0),
+            Self::PositiveDown => error_if_out_of_range(&inclination, -90.0, 90.0).map(|i| -i),
+            Self::FromVertical => error_if_out_of_range(&inclination, 0.0, 180.0).map(|i| i - 90.0),
+        }
+    }
+
+    /// Converts an inclination (in degrees) in the `NegativeDown` convention to this convention.
+    pub fn from_negative_down(&self, inclination: f64) -> f64 {
+        match self {
+            Self::NegativeDown => inclination,
+            Self::PositiveDown => -inclination,
+            Self::FromVertical => inclination + 90.0,
+        }
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Check the bearings and inclinations of a survey, convert it to the `NegativeDown` convention
+/// and sort it by depth.
+pub(crate) fn validate_survey(
+    hole_orientation: Vec<BHOrientation>,
+    inclination_convention: InclinationConvention,
+) -> Result<Vec<BHOrientation>, String> {
+    let mut hole_orientation = hole_orientation
+        .into_iter()
+        .map(|orientation| {
+            error_if_out_of_range(&orientation.bearing, 0.0, 360.0)
+                .map_err(|error| format!("Survey bearing at {}: {error}", orientation.depth))?;
+            let inclination = inclination_convention
+                .try_to_negative_down(orientation.inclination)
+                .map_err(|error| format!("Survey inclination at {}: {error}", orientation.depth))?;
+            Ok(BHOrientation {
+                inclination,
+                ..orientation
+            })
+        })
+        .collect::<Result<Vec<BHOrientation>, String>>()?;
+    hole_orientation.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+    Ok(hole_orientation)
+}
+
+/// Records that may belong to a named hole.
+pub trait HoleRecord {
+    fn hole_id(&self) -> Option<&str>;
+}
+
+/// Group records by their hole id, records without one are grouped under `default_hole_id`.
+/// The order of records within each hole is kept.
+pub fn group_by_hole<T: HoleRecord>(
+    records: impl IntoIterator<Item = T>,
+    default_hole_id: &str,
+) -> BTreeMap<String, Vec<T>> {
+    let mut holes: BTreeMap<String, Vec<T>> = BTreeMap::new();
+    for record in records {
+        let hole_id = record.hole_id().unwrap_or(default_hole_id).to_string();
+        holes.entry(hole_id).or_default().push(record);
+    }
+    holes
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RawMeasurement {
+    /// The hole the measurement was taken in, only needed when measurements from several holes are mixed
+    #[serde(default)]
+    pub hole_id: Option<String>,
     pub depth: f64,
     pub alpha: f64,
-    pub beta: f64,
+    /// `None` for measurements in unoriented core where only alpha could be recorded
+    pub beta: Option<f64>,
+    /// The orientation line beta was measured from.
+    /// Falls back to the orientation line of the `CoreRun` and then the `Borehole` when not set.
+    #[serde(default)]
+    pub orientation_line: Option<BHOrientationLine>,
+    /// Any other columns of the measurement, e.g. the structure type or comments.
+    /// Filled in by the reader of the table, they are carried through to the `OrientedMeasurement`.
+    #[serde(skip_deserializing)]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// A structural measurement after it has been oriented against the hole survey.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrientedMeasurement {
+    pub depth: f64,
+    /// The measurement as it was recorded
+    pub raw: RawMeasurement,
+    /// The bearing of the hole the measurement was oriented with
+    pub bearing: f64,
+    /// The inclination of the hole the measurement was oriented with, in the `NegativeDown` convention
+    pub inclination: f64,
+    /// The north the bearing and the azimuths of the plane, cone and uncertainty are measured from
+    pub north: AzimuthDatum,
+    /// Where the measurement is, see `Borehole::set_collar`
+    pub location: Option<Location>,
+    /// The confidence of the orientation mark on the run the measurement was taken from.
+    /// `None` when no core runs were supplied or the measurement falls outside all of them.
+    pub confidence: Option<OrientationConfidence>,
+    /// The oriented plane. `None` when the measurement was taken from an unoriented run or has no beta.
+    pub plane: Option<Plane>,
+    /// The cone of possible poles for measurements that could not be oriented.
+    pub cone: Option<AlphaCone>,
+    /// The QA flags of the survey station the measurement was oriented with, see `Borehole::check_survey`
+    pub survey_flags: Vec<SurveyFlag>,
+    /// The uncertainty of the pole, see `Borehole::estimate_uncertainty` and `Borehole::propagate_errors`.
+    pub uncertainty: Option<PoleUncertainty>,
+    /// The covariance (in radians²) of the unit pole vector in east, north and up coordinates,
+    /// with north being `north`, see `Borehole::propagate_errors`.
+    pub covariance: Option<[[f64; 3]; 3]>,
+    /// How sensitive the pole is to each input, see `Borehole::propagate_errors`.
+    pub sensitivities: Option<Sensitivities>,
+    /// The inputs the plane was oriented from
+    #[serde(skip)]
+    orient: Option<Orient>,
+    /// The clockwise angle in degrees from true north to `north`
+    #[serde(skip)]
+    north_rotation: f64,
+}
+
+impl OrientedMeasurement {
+    pub fn is_oriented(&self) -> bool {
+        self.plane.is_some()
+    }
+
+    /// Estimate the uncertainty of the pole by Monte Carlo simulation, see `Orient::monte_carlo`.
+    pub fn estimate_uncertainty(&mut self, errors: &MeasurementErrors, samples: usize, seed: u64) {
+        self.uncertainty = self
+            .orient
+            .map(|orient| orient.monte_carlo(errors, samples, seed))
+            .map(|uncertainty| rotate_uncertainty(uncertainty, -self.north_rotation));
+    }
+
+    /// Estimate the uncertainty of the pole with first order error propagation, see `Orient::propagate_errors`.
+    pub fn propagate_errors(&mut self, errors: &MeasurementErrors) {
+        let propagated = self.orient.map(|orient| orient.propagate_errors(errors));
+        self.uncertainty = propagated
+            .map(|propagated| rotate_uncertainty(propagated.uncertainty, -self.north_rotation));
+        self.covariance = propagated
+            .map(|propagated| rotate_covariance(propagated.covariance, -self.north_rotation));
+        self.sensitivities = propagated.map(|propagated| propagated.sensitivities);
+    }
+
+    /// Refer the azimuths to another north. `rotation` is the clockwise angle in degrees from true north
+    /// to that north, e.g. the grid convergence. Uncertainties estimated afterwards are rotated as well.
+    pub fn set_north(&mut self, north: AzimuthDatum, rotation: f64) {
+        let angle = self.north_rotation - rotation;
+        self.bearing = normalise_azimuth(self.bearing + angle);
+        self.plane = self.plane.map(|plane| plane.rotate_azimuth(angle));
+        self.cone = self.cone.map(|cone| AlphaCone {
+            axis: cone.axis.rotate_azimuth(angle),
+            ..cone
+        });
+        self.uncertainty = self
+            .uncertainty
+            .map(|uncertainty| rotate_uncertainty(uncertainty, angle));
+        self.covariance = self
+            .covariance
+            .map(|covariance| rotate_covariance(covariance, angle));
+        self.north = north;
+        self.north_rotation = rotation;
+    }
+}
+
+fn rotate_uncertainty(uncertainty: PoleUncertainty, angle: f64) -> PoleUncertainty {
+    PoleUncertainty {
+        ellipse: ErrorEllipse {
+            major_axis: uncertainty.ellipse.major_axis.rotate_azimuth(angle),
+            ..uncertainty.ellipse
+        },
+        ..uncertainty
+    }
+}
+
+/// Rotate a covariance in east, north and up coordinates with the azimuths, clockwise by `angle` degrees
+fn rotate_covariance(covariance: [[f64; 3]; 3], angle: f64) -> [[f64; 3]; 3] {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let rotation = Matrix3::new(cos, sin, 0.0, -sin, cos, 0.0, 0.0, 0.0, 1.0);
+    let covariance = Matrix3::from(covariance);
+    (rotation * covariance * rotation.transpose()).into()
+}
+
+impl HoleRecord for RawMeasurement {
+    fn hole_id(&self) -> Option<&str> {
+        self.hole_id.as_deref()
+    }
+}
+
+/// A measurement that could not be oriented and the reason why.
+#[derive(Debug, Clone, Serialize)]
+pub struct MeasurementFailure {
+    pub depth: f64,
+    pub reason: String,
+}
+
+impl fmt::Display for MeasurementFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "measurement at {} was not oriented: {}",
+            self.depth, self.reason
+        )
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct BHOrientation {
+    /// The hole the survey station belongs to, only needed when surveys from several holes are mixed
+    #[serde(default)]
+    pub hole_id: Option<String>,
+    /// The survey the station was taken in, e.g. `gyro` or `magnetic`, see `merge_surveys`
+    #[serde(default)]
+    pub survey_type: Option<String>,
     pub depth: f64,
     pub bearing: f64,
     pub inclination: f64,
 }
 
+impl HoleRecord for BHOrientation {
+    fn hole_id(&self) -> Option<&str> {
+        self.hole_id.as_deref()
+    }
+}
+
 pub struct Borehole {
     /// Oriented structural measurements with alpha and beta angles (in degrees) relative to the borehole `orientation_line`
-    pub oriented_measurements: Vec<Plane>,
-    /// The location of the orientation line on the borehole
+    pub oriented_measurements: Vec<OrientedMeasurement>,
+    /// Measurements that could not be oriented, e.g. because they are out of range or deeper than the survey
+    pub failed_measurements: Vec<MeasurementFailure>,
+    /// The location of the orientation line on the borehole.
+    /// Used for measurements that do not record their own orientation line.
     pub orientation_line: BHOrientationLine,
+    /// The convention the survey inclinations were recorded in
+    pub inclination_convention: InclinationConvention,
+    /// The convention the beta angles were measured in
+    pub beta_convention: BetaConvention,
     /// A vector of hole depths with bearing and inclination.
+    /// Inclinations are stored in the `NegativeDown` convention.
     /// The fist value MUST have depth=0.0
     pub hole_orientation: Vec<BHOrientation>,
+    /// Drill runs with the confidence and orientation line of their orientation mark
+    pub core_runs: Vec<CoreRun>,
+    /// The collar of the hole, when the borehole was loaded from a `Project`
+    pub collar: Option<Collar>,
+    /// The north the azimuths of the oriented measurements refer to, see `set_north`.
+    /// The survey is always referred to true north.
+    pub north: AzimuthDatum,
+    /// The clockwise angle in degrees from true north to `north`
+    pub north_rotation: f64,
+    /// The meridian convergence at the collar in degrees when the collar coordinates are on a map grid,
+    /// see `set_grid_convergence`
+    grid_convergence: f64,
+    /// The depth interval each survey station is used for, see `survey_depth_intervals`
+    depth_intervals: Vec<(f64, f64)>,
+    /// The survey stations located from the collar with bearings referred to grid north,
+    /// `None` when the collar is unknown
+    located_survey: Option<DesurveyedHole>,
+    /// The depth interval of each station of the checked survey, empty until `check_survey` is called
+    qa_intervals: Vec<(f64, f64)>,
+    /// The checks of each survey station, empty until `check_survey` is called
+    pub survey_qa: Vec<StationQa>,
+    /// Where overridden surveys disagree with the chosen survey, when the borehole was loaded from a `Project`
+    pub survey_disagreements: Vec<SurveyDisagreement>,
+    /// What smoothing did to each survey station, when the borehole was loaded from a `Project`
+    /// with `survey_smoothing` set
+    pub smoothed_survey: Vec<SmoothedStation>,
+    /// The merged survey before smoothing in the `NegativeDown` convention, checked by `check_survey`.
+    /// Empty when the survey was not smoothed.
+    pub unsmoothed_survey: Vec<BHOrientation>,
 }
 
 impl Borehole {
+    /// Panics if the survey is invalid, see `try_new`.
     pub fn new(
         orientation_line: BHOrientationLine,
+        inclination_convention: InclinationConvention,
+        beta_convention: BetaConvention,
         raw_measurements: Vec<RawMeasurement>,
         hole_orientation: Vec<BHOrientation>,
+        core_runs: Vec<CoreRun>,
     ) -> Self {
-        Self {
-            oriented_measurements: map_measurements_to_depths(
-                raw_measurements,
-                &hole_orientation,
-                &orientation_line,
-            ),
+        Self::try_new(
+            orientation_line,
+            inclination_convention,
+            beta_convention,
+            raw_measurements,
+            hole_orientation,
+            core_runs,
+        )
+        .unwrap()
+    }
+
+    /// Orient the measurements of a hole.
+    /// Returns an error if the survey cannot be used. Measurements that cannot be oriented are
+    /// collected in `failed_measurements` rather than failing the whole hole.
+    pub fn try_new(
+        orientation_line: BHOrientationLine,
+        inclination_convention: InclinationConvention,
+        beta_convention: BetaConvention,
+        raw_measurements: Vec<RawMeasurement>,
+        hole_orientation: Vec<BHOrientation>,
+        core_runs: Vec<CoreRun>,
+    ) -> Result<Self, String> {
+        let hole_orientation = validate_survey(hole_orientation, inclination_convention)?;
+
+        match hole_orientation.first() {
+            None => return Err("The survey has no stations".to_string()),
+            Some(first) if first.depth != 0.0 => {
+                return Err(format!(
+                    "The first survey station must be at depth 0.0, found {}",
+                    first.depth
+                ))
+            }
+            _ => (),
+        }
+
+        let depth_intervals = survey_depth_intervals(&hole_orientation);
+        let (oriented_measurements, failed_measurements) = map_measurements_to_depths(
+            raw_measurements,
+            &depth_intervals,
+            &hole_orientation,
+            &core_runs,
+            &orientation_line,
+            &beta_convention,
+        );
+
+        Ok(Self {
+            oriented_measurements,
+            failed_measurements,
             orientation_line,
+            inclination_convention,
+            beta_convention,
             hole_orientation,
+            core_runs,
+            collar: None,
+            north: AzimuthDatum::True,
+            north_rotation: 0.0,
+            grid_convergence: 0.0,
+            depth_intervals,
+            located_survey: None,
+            qa_intervals: vec![],
+            survey_qa: vec![],
+            survey_disagreements: vec![],
+            smoothed_survey: vec![],
+            unsmoothed_survey: vec![],
+        })
+    }
+
+    /// Estimate the uncertainty of every oriented measurement by Monte Carlo simulation.
+    /// See `Orient::monte_carlo`, the seed of each measurement is offset by its index.
+    pub fn estimate_uncertainty(&mut self, errors: &MeasurementErrors, samples: usize, seed: u64) {
+        for (index, measurement) in self.oriented_measurements.iter_mut().enumerate() {
+            measurement.estimate_uncertainty(errors, samples, seed.wrapping_add(index as u64));
+        }
+    }
+
+    /// Estimate the uncertainty of every oriented measurement with first order error propagation.
+    /// See `Orient::propagate_errors`.
+    pub fn propagate_errors(&mut self, errors: &MeasurementErrors) {
+        for measurement in self.oriented_measurements.iter_mut() {
+            measurement.propagate_errors(errors);
+        }
+    }
+
+    /// Orient a single measurement against the survey of the hole without storing it.
+    /// Useful for streaming measurement tables that are too large to hold in memory.
+    pub fn orient(
+        &self,
+        measurement: RawMeasurement,
+    ) -> Result<OrientedMeasurement, MeasurementFailure> {
+        let depth = measurement.depth;
+        let mut oriented = orient_measurement(
+            measurement,
+            &self.depth_intervals,
+            &self.hole_orientation,
+            &self.core_runs,
+            &self.orientation_line,
+            &self.beta_convention,
+        )
+        .map_err(|reason| MeasurementFailure { depth, reason })?;
+        oriented.location = self.location_at(depth);
+        oriented.set_north(self.north, self.north_rotation);
+        oriented.survey_flags = self.survey_flags_at(depth);
+        Ok(oriented)
+    }
+
+    /// Check the survey for doglegs, jumps and outliers, see `survey_qa`. A smoothed survey is checked
+    /// before smoothing, see `unsmoothed_survey`, as smoothing removes the stations the checks look for.
+    /// Measurements oriented near a flagged station get its flags.
+    pub fn check_survey(&mut self, limits: &SurveyQaLimits) {
+        self.survey_qa = survey_qa(self.checked_survey(), limits);
+        self.qa_intervals = survey_depth_intervals(self.checked_survey());
+        let flags = self
+            .oriented_measurements
+            .iter()
+            .map(|measurement| self.survey_flags_at(measurement.depth))
+            .collect::<Vec<Vec<SurveyFlag>>>();
+        for (measurement, flags) in self.oriented_measurements.iter_mut().zip(flags) {
+            measurement.survey_flags = flags;
+        }
+    }
+
+    /// The QA flags of the survey station used at `depth`, empty before `check_survey`
+    fn survey_flags_at(&self, depth: f64) -> Vec<SurveyFlag> {
+        if self.survey_qa.is_empty() {
+            return vec![];
         }
+        station_index(&self.qa_intervals, depth)
+            .map(|index| self.survey_qa[index].flags.clone())
+            .unwrap_or_default()
+    }
+
+    /// The survey `check_survey` checks
+    fn checked_survey(&self) -> &[BHOrientation] {
+        match self.unsmoothed_survey.is_empty() {
+            true => &self.hole_orientation,
+            false => &self.unsmoothed_survey,
+        }
+    }
+
+    /// Refer the azimuths of the oriented measurements to another north, see `OrientedMeasurement::set_north`.
+    pub fn set_north(&mut self, north: AzimuthDatum, rotation: f64) {
+        for measurement in self.oriented_measurements.iter_mut() {
+            measurement.set_north(north, rotation);
+        }
+        self.north = north;
+        self.north_rotation = rotation;
+    }
+
+    /// Set the collar of the hole and locate the measurements along the hole.
+    pub fn set_collar(&mut self, collar: Collar) {
+        self.collar = Some(collar);
+        self.locate_survey();
+        let locations = self
+            .oriented_measurements
+            .iter()
+            .map(|measurement| self.location_at(measurement.depth))
+            .collect::<Vec<Option<Location>>>();
+        for (measurement, location) in self.oriented_measurements.iter_mut().zip(locations) {
+            measurement.location = location;
+        }
+    }
+
+    /// The meridian convergence at the collar in degrees, see `set_grid_convergence`
+    pub fn grid_convergence(&self) -> f64 {
+        self.grid_convergence
+    }
+
+    /// Set the meridian convergence at the collar when the collar coordinates are on a map grid.
+    /// The survey is rotated by it to grid north to locate the measurements.
+    pub fn set_grid_convergence(&mut self, convergence: f64) {
+        self.grid_convergence = convergence;
+        if let Some(collar) = self.collar.take() {
+            self.set_collar(collar);
+        }
+    }
+
+    /// Locate the survey stations from the collar once, rotated to grid north by the grid convergence
+    fn locate_survey(&mut self) {
+        self.located_survey = self.collar.as_ref().map(|collar| {
+            let survey = self
+                .hole_orientation
+                .iter()
+                .map(|station| BHOrientation {
+                    bearing: normalise_azimuth(station.bearing - self.grid_convergence),
+                    ..station.clone()
+                })
+                .collect::<Vec<BHOrientation>>();
+            DesurveyedHole::new(collar, &survey)
+        });
+    }
+
+    /// Returns the location at `depth` along the hole, `None` when the collar is unknown.
+    pub fn location_at(&self, depth: f64) -> Option<Location> {
+        self.located_survey
+            .as_ref()
+            .map(|survey| survey.location_at(depth))
+    }
+
+    /// Returns the survey station used to orient structures at `depth`, i.e. the nearest station.
+    pub fn orientation_at(&self, depth: f64) -> Option<&BHOrientation> {
+        station_index(&self.depth_intervals, depth).map(|index| &self.hole_orientation[index])
     }
 }
 
-fn map_measurements_to_depths(
-    raw_measurements: Vec<RawMeasurement>,
-    raw_orientation: &[BHOrientation],
-    orientation_line: &BHOrientationLine,
-) -> Vec<Plane> {
+/// Returns the depth interval each survey station is used for.
+/// Each interval extends half way to the neighbouring stations.
+/// A single station (e.g. the planned collar orientation) is used for the whole hole.
+fn survey_depth_intervals(raw_orientation: &[BHOrientation]) -> Vec<(f64, f64)> {
     // error if the first raw_orientation depth is not 0.0
     if raw_orientation[0].depth != 0.0 {
         panic!("The first raw_orientation depth must be 0.0");
     }
+    if raw_orientation.len() == 1 {
+        return vec![(f64::NEG_INFINITY, f64::INFINITY)];
+    }
 
     let mut depth_pairs: Vec<(f64, f64)> = vec![];
     let last_index = raw_orientation.len() - 1;
     for (i, measurement) in raw_orientation.iter().enumerate() {
         match i {
+            // Start below zero so that structures at the collar are included
             0 => depth_pairs.push((
-                measurement.depth,
+                f64::NEG_INFINITY,
                 (raw_orientation[i + 1].depth - measurement.depth) / 2.0,
             )),
             _ if i == last_index => depth_pairs.push((
@@ -94,31 +675,118 @@ fn map_measurements_to_depths(
             _ => panic!("This should never happen"),
         }
     }
+    depth_pairs
+}
 
-    raw_measurements
-        .into_iter()
-        .map(|measurement| {
-            let index = depth_pairs.binary_search_by(|(first, last)| {
-                if measurement.depth > *first && measurement.depth <= *last {
-                    Ordering::Equal
-                } else if measurement.depth <= *first {
-                    Ordering::Greater
-                } else {
-                    Ordering::Less
-                }
-            });
-            let index = index.unwrap();
-
-            let orientation = &raw_orientation[index];
-            Plane::alpha_beta(
+/// Returns the index of the survey station whose interval contains `depth`.
+fn station_index(depth_pairs: &[(f64, f64)], depth: f64) -> Option<usize> {
+    depth_pairs
+        .binary_search_by(|(first, last)| {
+            if depth > *first && depth <= *last {
+                Ordering::Equal
+            } else if depth <= *first {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        })
+        .ok()
+}
+
+fn map_measurements_to_depths(
+    raw_measurements: Vec<RawMeasurement>,
+    depth_pairs: &[(f64, f64)],
+    raw_orientation: &[BHOrientation],
+    core_runs: &[CoreRun],
+    orientation_line: &BHOrientationLine,
+    beta_convention: &BetaConvention,
+) -> (Vec<OrientedMeasurement>, Vec<MeasurementFailure>) {
+    let mut oriented_measurements = vec![];
+    let mut failed_measurements = vec![];
+    for measurement in raw_measurements {
+        let depth = measurement.depth;
+        match orient_measurement(
+            measurement,
+            depth_pairs,
+            raw_orientation,
+            core_runs,
+            orientation_line,
+            beta_convention,
+        ) {
+            Ok(oriented) => oriented_measurements.push(oriented),
+            Err(reason) => failed_measurements.push(MeasurementFailure { depth, reason }),
+        }
+    }
+    (oriented_measurements, failed_measurements)
+}
+
+fn orient_measurement(
+    measurement: RawMeasurement,
+    depth_pairs: &[(f64, f64)],
+    raw_orientation: &[BHOrientation],
+    core_runs: &[CoreRun],
+    orientation_line: &BHOrientationLine,
+    beta_convention: &BetaConvention,
+) -> Result<OrientedMeasurement, String> {
+    if measurement.depth < 0.0 {
+        return Err(format!("Depth {} is negative", measurement.depth));
+    }
+    let index = station_index(depth_pairs, measurement.depth).ok_or_else(|| {
+        format!(
+            "Depth {} is deeper than the last survey station",
+            measurement.depth
+        )
+    })?;
+    error_if_out_of_range(&measurement.alpha, 0.0, 90.0)
+        .map_err(|error| format!("Alpha: {error}"))?;
+
+    let orientation = &raw_orientation[index];
+    let run = run_at_depth(core_runs, measurement.depth);
+    let confidence = run.map(|run| run.confidence);
+
+    let orient = match (confidence, measurement.beta) {
+        (Some(OrientationConfidence::Unoriented), _) | (_, None) => None,
+        (_, Some(beta)) => {
+            error_if_out_of_range(&beta, 0.0, 360.0).map_err(|error| format!("Beta: {error}"))?;
+            Some(Orient::new(
                 orientation.bearing,
                 orientation.inclination,
                 measurement.alpha,
-                measurement.beta,
-                *orientation_line,
-            )
-        })
-        .collect::<Vec<Plane>>()
+                beta_convention.to_internal(beta),
+                measurement
+                    .orientation_line
+                    .or(run.and_then(|run| run.orientation_line))
+                    .unwrap_or(*orientation_line),
+            ))
+        }
+    };
+    let plane = orient.map(Orient::into_plane);
+    let cone = match plane {
+        Some(_) => None,
+        None => Some(AlphaCone::new(
+            orientation.bearing,
+            orientation.inclination,
+            measurement.alpha,
+        )),
+    };
+
+    Ok(OrientedMeasurement {
+        depth: measurement.depth,
+        bearing: orientation.bearing,
+        inclination: orientation.inclination,
+        north: AzimuthDatum::True,
+        location: None,
+        raw: measurement,
+        confidence,
+        plane,
+        cone,
+        survey_flags: vec![],
+        uncertainty: None,
+        covariance: None,
+        sensitivities: None,
+        orient,
+        north_rotation: 0.0,
+    })
 }
 
 /// Definitions from https://www.sciencedirect.com/science/article/pii/S0098300413000551
@@ -127,17 +795,17 @@ fn map_measurements_to_depths(
 pub struct Orient {
     /// The angle between North and the borehole trajectory projected to the horizontal.
     /// The angle is measured clockwise from north and has a positive value between 0° and 360°.
-    bearing: f64,
+    pub(crate) bearing: f64,
     /// Is defined as the acute angle between the horizontal plane and the trajectory of the borehole.
     /// The angle is measured from the horizontal plane and has a value between 0° and 90°.
     /// It is negative if the borehole trajectory is pointing downwards.
-    inclination: f64,
+    pub(crate) inclination: f64,
     /// The acute dihedral angle between the fracture plane and the trajectory of the borehole.
     /// The angle is restricted to be between 0° and 90°, where 90° corresponds to a fracture perpendicular to the borehole.
-    alpha: f64,
+    pub(crate) alpha: f64,
     /// The angle from a reference line (in this paper defined as the line of the top of the roof of the borehole profile) to the lower inflexion point of the fracture trace on the borehole wall,
     ///  The angle is measured clockwise looking in the direction of the borehole trajectory and can hence be between 0° and 360°
-    beta: f64,
+    pub(crate) beta: f64,
 }
 
 impl Orient {
@@ -153,14 +821,7 @@ impl Orient {
         error_if_out_of_range(&alpha, 0.0, 90.0).unwrap();
         error_if_out_of_range(&beta, 0.0, 360.0).unwrap();
 
-        let beta = match orientation_line {
-            BHOrientationLine::Top => beta,
-            BHOrientationLine::Bottom => match beta + 180.0 {
-                // ensure beta is between 0 and 360
-                x if x > 360.0 => x - 360.0,
-                x => x,
-            },
-        };
+        let beta = normalise_azimuth(beta + orientation_line.offset());
 
         Self {
             bearing: bearing.to_radians(),
@@ -173,37 +834,18 @@ impl Orient {
     /// Returns an oriented `Plane` while consuming the `Orient` struct.
     pub fn into_plane(self) -> Plane {
         let (trend, plunge) = self.trend_and_plunge();
-        let strike = strike_from_trend(&trend.to_degrees());
-
-        Plane::new(
-            strike,
-            dip_from_plunge(&plunge.to_degrees()),
-            Some(dip_direction_from_strike(&strike)),
-            Some(trend.to_degrees()),
-            Some(plunge.to_degrees()),
-        )
+        Plane::from_pole(Lineation::new(trend.to_degrees(), plunge.to_degrees()))
     }
 
     /// Returns the orientation of the pole to the measured plane (trend, plunge)
+    /// The pole is always the downward pointing normal, so planes intersected by up-holes
+    /// (and by shallow down-holes at low alpha) still plot in the lower hemisphere.
     fn trend_and_plunge(&self) -> (f64, f64) {
-        let n_g = self.normal_g();
-        let apparent_trend = (n_g.x / (n_g.x.powi(2) + n_g.y.powi(2)).sqrt()).acos();
-
-        let mut trend = if n_g.y <= 0.0 {
-            FRAC_PI_2 + apparent_trend
-        } else {
-            FRAC_PI_2 - apparent_trend
-        };
-
-        if trend < 0.0 {
-            trend += PI * 2.0;
-        }
-
-        (trend, -n_g.z.asin())
+        trend_and_plunge_from_vector(&self.normal_g())
     }
 
     /// The normal vector of the measured plane relative to the borehole
-    fn normal_bh(&self) -> Vector3<f64> {
+    pub(crate) fn normal_bh(&self) -> Vector3<f64> {
         let alpha = self.alpha;
         let beta = self.beta;
 
@@ -215,7 +857,7 @@ impl Orient {
     }
 
     /// The normal vector of the measured plane relative to the global coordinate system
-    fn normal_g(&self) -> Vector3<f64> {
+    pub(crate) fn normal_g(&self) -> Vector3<f64> {
         let z_rot = self.z_rot();
         let y_rot = self.y_rot();
         let bh_normal = self.normal_bh();
@@ -223,12 +865,12 @@ impl Orient {
         z_rot * y_rot * bh_normal
     }
 
-    fn y_rot(&self) -> Matrix3<f64> {
+    pub(crate) fn y_rot(&self) -> Matrix3<f64> {
         let i = FRAC_PI_2 - self.inclination;
         Matrix3::new(i.cos(), 0.0, i.sin(), 0.0, 1.0, 0.0, -i.sin(), 0.0, i.cos())
     }
 
-    fn z_rot(&self) -> Matrix3<f64> {
+    pub(crate) fn z_rot(&self) -> Matrix3<f64> {
         let b = FRAC_PI_2 - self.bearing;
         Matrix3::new(b.cos(), -b.sin(), 0.0, b.sin(), b.cos(), 0.0, 0.0, 0.0, 1.0)
     }
@@ -313,6 +955,574 @@ mod tests {
         assert_eq!(plane.pole.plunge.round(), 45.0);
     }
 
+    /**
+     * Up-hole drilled from underground intersecting a plane perpendicular to the hole.
+     * The pole is the hole axis reversed so that it points downward.
+     *        /
+     *   \\  / (bearing=0.0, inclination=45.0)
+     *    \\/
+     *    /\\  Shear plane (alpha=90.0, beta=180.0) = (trend=180.0, plunge=45.0)
+     * __/__\\________________________________________
+     */
+    #[test]
+    fn orient_up_hole_perpendicular_plane() {
+        let plane = Orient::new(0.0, 45.0, 90.0, 180.0, BHOrientationLine::Top).into_plane();
+
+        assert_eq!(plane.pole.trend.round(), 180.0);
+        assert_eq!(plane.pole.plunge.round(), 45.0);
+        assert_eq!(plane.strike.round(), 270.0);
+        assert_eq!(plane.dip.round(), 45.0);
+        assert_eq!(plane.dip_direction.round(), 360.0);
+    }
+
+    #[test]
+    fn orient_up_hole_top_line_is_roof() {
+        // The lower inflexion point sits on the roof of the hole, so the plane
+        // is vertical and strikes across the hole.
+        let (trend, plunge) =
+            Orient::new(0.0, 45.0, 45.0, 0.0, BHOrientationLine::Top).trend_and_plunge();
+
+        assert_eq!(trend.to_degrees().round(), 0.0);
+        assert_eq!(plunge.to_degrees().round(), 0.0);
+    }
+
+    #[test]
+    fn orient_up_hole_bottom_line() {
+        let top = Orient::new(125.0, 30.0, 40.0, 250.0, BHOrientationLine::Top).into_plane();
+        let bottom = Orient::new(125.0, 30.0, 40.0, 70.0, BHOrientationLine::Bottom).into_plane();
+
+        assert_eq!(top.pole.trend.round(), bottom.pole.trend.round());
+        assert_eq!(top.pole.plunge.round(), bottom.pole.plunge.round());
+    }
+
+    #[test]
+    fn orient_shallow_hole_upward_normal() {
+        // The normal to this plane points upward and must be flipped into the lower hemisphere
+        let plane = Orient::new(0.0, -10.0, 10.0, 180.0, BHOrientationLine::Top).into_plane();
+
+        assert_eq!(plane.pole.trend.round(), 180.0);
+        assert_eq!(plane.pole.plunge.round(), 70.0);
+    }
+
+    #[test]
+    fn orient_vertical_hole_uses_bearing_as_reference() {
+        let (trend, plunge) =
+            Orient::new(90.0, -90.0, 30.0, 0.0, BHOrientationLine::Top).trend_and_plunge();
+        assert_eq!(trend.to_degrees().round(), 270.0);
+        assert_eq!(plunge.to_degrees().round(), 30.0);
+
+        // The roof of an up-hole leans back towards the collar
+        let (trend, plunge) =
+            Orient::new(90.0, 90.0, 30.0, 0.0, BHOrientationLine::Top).trend_and_plunge();
+        assert_eq!(trend.to_degrees().round(), 270.0);
+        assert_eq!(plunge.to_degrees().round(), 30.0);
+    }
+
+    #[test]
+    fn orient_horizontal_plane_has_vertical_pole() {
+        let (trend, plunge) =
+            Orient::new(0.0, -90.0, 90.0, 0.0, BHOrientationLine::Top).trend_and_plunge();
+
+        assert_eq!(trend.to_degrees(), 0.0);
+        assert_eq!(plunge.to_degrees().round(), 90.0);
+    }
+
+    #[test]
+    fn alpha_beta_candidates_for_ambiguous_mark() {
+        let [top, bottom] = Plane::alpha_beta_candidates(0.0, 45.0, 45.0, 0.0);
+
+        assert_eq!(top.pole.trend.round(), 0.0);
+        assert_eq!(top.pole.plunge.round(), 0.0);
+        assert_eq!(bottom.pole.trend.round(), 0.0);
+        assert_eq!(bottom.pole.plunge.round(), 90.0);
+    }
+
+    #[test]
+    fn borehole_up_hole_measurements() {
+        let hole_orientation = vec![
+            BHOrientation {
+                hole_id: None,
+                survey_type: None,
+                depth: 0.0,
+                bearing: 0.0,
+                inclination: 45.0,
+            },
+            BHOrientation {
+                hole_id: None,
+                survey_type: None,
+                depth: 50.0,
+                bearing: 0.0,
+                inclination: 45.0,
+            },
+        ];
+        let raw_measurements = vec![
+            RawMeasurement {
+                hole_id: None,
+                depth: 20.0,
+                alpha: 90.0,
+                beta: Some(0.0),
+                orientation_line: None,
+                extra: BTreeMap::new(),
+            },
+            RawMeasurement {
+                hole_id: None,
+                depth: 30.0,
+                alpha: 45.0,
+                beta: Some(0.0),
+                orientation_line: Some(BHOrientationLine::Bottom),
+                extra: BTreeMap::new(),
+            },
+        ];
+        let borehole = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            raw_measurements,
+            hole_orientation,
+            vec![],
+        );
+
+        let plane = borehole.oriented_measurements[0].plane.unwrap();
+        assert_eq!(plane.pole.trend.round(), 180.0);
+        assert_eq!(plane.pole.plunge.round(), 45.0);
+
+        let plane = borehole.oriented_measurements[1].plane.unwrap();
+        assert_eq!(plane.pole.plunge.round(), 90.0);
+    }
+
+    #[test]
+    fn borehole_alpha_only_measurement() {
+        let hole_orientation = vec![
+            BHOrientation {
+                hole_id: None,
+                survey_type: None,
+                depth: 0.0,
+                bearing: 90.0,
+                inclination: -60.0,
+            },
+            BHOrientation {
+                hole_id: None,
+                survey_type: None,
+                depth: 100.0,
+                bearing: 90.0,
+                inclination: -60.0,
+            },
+        ];
+        let raw_measurements = vec![RawMeasurement {
+            hole_id: None,
+            depth: 50.0,
+            alpha: 40.0,
+            beta: None,
+            orientation_line: None,
+            extra: BTreeMap::new(),
+        }];
+        let borehole = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            raw_measurements,
+            hole_orientation,
+            vec![],
+        );
+        let measurement = &borehole.oriented_measurements[0];
+
+        assert!(!measurement.is_oriented());
+        let cone = measurement.cone.unwrap();
+        assert_eq!(cone.axis.trend.round(), 90.0);
+        assert_eq!(cone.axis.plunge.round(), 60.0);
+        assert_eq!(cone.half_angle.round(), 50.0);
+
+        let orientation = borehole.orientation_at(50.0).unwrap();
+        assert_eq!(orientation.inclination, -60.0);
+        assert!(borehole.orientation_at(150.0).is_none());
+    }
+
+    #[test]
+    fn borehole_core_runs() {
+        let hole_orientation = vec![
+            BHOrientation {
+                hole_id: None,
+                survey_type: None,
+                depth: 0.0,
+                bearing: 262.7,
+                inclination: -55.3,
+            },
+            BHOrientation {
+                hole_id: None,
+                survey_type: None,
+                depth: 100.0,
+                bearing: 262.7,
+                inclination: -55.3,
+            },
+        ];
+        let raw_measurements = [10.0, 20.0, 30.0, 40.0]
+            .into_iter()
+            .map(|depth| RawMeasurement {
+                hole_id: None,
+                depth,
+                alpha: 65.0,
+                beta: Some(230.0),
+                orientation_line: None,
+                extra: BTreeMap::new(),
+            })
+            .collect();
+        let core_runs = vec![
+            CoreRun {
+                hole_id: None,
+                from: 0.0,
+                to: 15.0,
+                confidence: OrientationConfidence::Good,
+                orientation_line: None,
+            },
+            CoreRun {
+                hole_id: None,
+                from: 15.0,
+                to: 25.0,
+                confidence: OrientationConfidence::Unoriented,
+                orientation_line: None,
+            },
+            CoreRun {
+                hole_id: None,
+                from: 25.0,
+                to: 35.0,
+                confidence: OrientationConfidence::Poor,
+                orientation_line: Some(BHOrientationLine::Bottom),
+            },
+        ];
+        let borehole = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            raw_measurements,
+            hole_orientation,
+            core_runs,
+        );
+        let measurements = &borehole.oriented_measurements;
+
+        assert_eq!(
+            measurements[0].confidence,
+            Some(OrientationConfidence::Good)
+        );
+        assert_eq!(measurements[0].plane.unwrap().pole.trend.round(), 286.0);
+
+        assert_eq!(
+            measurements[1].confidence,
+            Some(OrientationConfidence::Unoriented)
+        );
+        assert!(!measurements[1].is_oriented());
+        assert_eq!(measurements[1].cone.unwrap().half_angle.round(), 25.0);
+
+        // The bottom mark on the third run flips beta by 180°
+        assert_eq!(
+            measurements[2].confidence,
+            Some(OrientationConfidence::Poor)
+        );
+        assert!(measurements[2].is_oriented());
+        assert!(measurements[2].cone.is_none());
+        let top = measurements[0].plane.unwrap();
+        let bottom = measurements[2].plane.unwrap();
+        let expected = Plane::alpha_beta(262.7, -55.3, 65.0, 230.0, BHOrientationLine::Bottom);
+        assert!((bottom.pole.trend - expected.pole.trend).abs() < 1e-9);
+        assert!((bottom.pole.plunge - expected.pole.plunge).abs() < 1e-9);
+        assert!((bottom.pole.trend - top.pole.trend).abs() > 1.0);
+
+        assert_eq!(measurements[3].confidence, None);
+        assert!(measurements[3].is_oriented());
+    }
+
+    #[test]
+    fn orient_new_ori_offset() {
+        let (trend, plunge) =
+            Orient::new(0.0, -45.0, 90.0, 90.0, BHOrientationLine::Offset(90.0)).trend_and_plunge();
+
+        assert_eq!(trend.to_degrees().round(), 0.0);
+        assert_eq!(plunge.to_degrees().round(), 45.0);
+
+        let offset = Orient::new(262.7, -55.3, 65.0, 200.0, BHOrientationLine::Offset(30.0))
+            .trend_and_plunge();
+        let top = Orient::new(262.7, -55.3, 65.0, 230.0, BHOrientationLine::Top).trend_and_plunge();
+        assert_eq!(offset.0.to_degrees().round(), top.0.to_degrees().round());
+        assert_eq!(offset.1.to_degrees().round(), top.1.to_degrees().round());
+    }
+
+    #[test]
+    fn orientation_line_from_str() {
+        assert_eq!("top".parse(), Ok(BHOrientationLine::Top));
+        assert_eq!("Bottom".parse(), Ok(BHOrientationLine::Bottom));
+        assert_eq!("45.5".parse(), Ok(BHOrientationLine::Offset(45.5)));
+        assert!("sideways".parse::<BHOrientationLine>().is_err());
+        assert!("400".parse::<BHOrientationLine>().is_err());
+    }
+
+    #[test]
+    fn inclination_conventions() {
+        assert_eq!(
+            InclinationConvention::NegativeDown.to_negative_down(-60.0),
+            -60.0
+        );
+        assert_eq!(
+            InclinationConvention::PositiveDown.to_negative_down(60.0),
+            -60.0
+        );
+        assert_eq!(
+            InclinationConvention::PositiveDown.to_negative_down(-20.0),
+            20.0
+        );
+        assert_eq!(
+            InclinationConvention::FromVertical.to_negative_down(30.0),
+            -60.0
+        );
+        assert_eq!(
+            InclinationConvention::FromVertical.to_negative_down(135.0),
+            45.0
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn inclination_convention_from_vertical_out_of_range() {
+        InclinationConvention::FromVertical.to_negative_down(-10.0);
+    }
+
+    #[test]
+    fn beta_conventions() {
+        use BetaApex::*;
+        use BetaDirection::*;
+
+        assert_eq!(BetaConvention::default().to_internal(230.0), 230.0);
+        assert_eq!(
+            BetaConvention::new(CounterClockwiseDownHole, Lower).to_internal(130.0),
+            230.0
+        );
+        assert_eq!(
+            BetaConvention::new(ClockwiseDownHole, Upper).to_internal(50.0),
+            230.0
+        );
+        assert_eq!(
+            BetaConvention::new(CounterClockwiseDownHole, Upper).to_internal(310.0),
+            230.0
+        );
+        assert_eq!(
+            BetaConvention::new(CounterClockwiseDownHole, Lower).to_internal(0.0),
+            0.0
+        );
+    }
+
+    fn station(hole_id: Option<&str>, depth: f64) -> BHOrientation {
+        BHOrientation {
+            hole_id: hole_id.map(String::from),
+            survey_type: None,
+            depth,
+            bearing: 262.7,
+            inclination: -55.3,
+        }
+    }
+
+    fn measurement(depth: f64, alpha: f64, beta: Option<f64>) -> RawMeasurement {
+        RawMeasurement {
+            hole_id: None,
+            depth,
+            alpha,
+            beta,
+            orientation_line: None,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn group_by_hole_keeps_order() {
+        let stations = vec![
+            station(Some("DH2"), 0.0),
+            station(Some("DH1"), 0.0),
+            station(None, 0.0),
+            station(Some("DH2"), 50.0),
+        ];
+        let holes = group_by_hole(stations, "default");
+
+        assert_eq!(
+            holes.keys().collect::<Vec<&String>>(),
+            vec!["DH1", "DH2", "default"]
+        );
+        assert_eq!(holes["DH2"][1].depth, 50.0);
+    }
+
+    #[test]
+    fn borehole_try_new_invalid_survey() {
+        let try_new = |hole_orientation| {
+            Borehole::try_new(
+                BHOrientationLine::Top,
+                InclinationConvention::NegativeDown,
+                BetaConvention::default(),
+                vec![],
+                hole_orientation,
+                vec![],
+            )
+        };
+
+        assert!(try_new(vec![]).is_err());
+        assert!(try_new(vec![station(None, 10.0)]).is_err());
+        assert!(try_new(vec![BHOrientation {
+            inclination: -120.0,
+            ..station(None, 0.0)
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn borehole_failed_measurements() {
+        let borehole = Borehole::try_new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            vec![
+                measurement(0.0, 65.0, Some(230.0)),
+                measurement(60.0, 65.0, Some(230.0)),
+                measurement(120.0, 65.0, Some(230.0)),
+                measurement(70.0, 95.0, Some(230.0)),
+                measurement(80.0, 65.0, Some(400.0)),
+            ],
+            // Stations out of order are sorted by depth
+            vec![station(None, 100.0), station(None, 0.0)],
+            vec![],
+        )
+        .unwrap();
+
+        assert_eq!(borehole.oriented_measurements.len(), 2);
+        assert_eq!(
+            borehole
+                .failed_measurements
+                .iter()
+                .map(|failure| failure.depth)
+                .collect::<Vec<f64>>(),
+            vec![120.0, 70.0, 80.0]
+        );
+    }
+
+    #[test]
+    fn borehole_pole_covariance_follows_north() {
+        let errors = MeasurementErrors {
+            bearing: 2.0,
+            inclination: 1.0,
+            alpha: 3.0,
+            beta: 5.0,
+        };
+        let mut borehole = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            vec![measurement(60.0, 65.0, Some(230.0))],
+            vec![station(None, 0.0), station(None, 100.0)],
+            vec![],
+        );
+        borehole.propagate_errors(&errors);
+        let covariance = borehole.oriented_measurements[0].covariance.unwrap();
+        let sensitivities = borehole.oriented_measurements[0].sensitivities.unwrap();
+        assert!(covariance[2][2] > 0.0);
+        assert!(sensitivities.beta > 0.0);
+
+        // Referring the azimuths 90° anticlockwise swaps east and north
+        borehole.set_north(AzimuthDatum::Grid, 90.0);
+        let rotated = borehole.oriented_measurements[0].covariance.unwrap();
+        assert!((rotated[0][0] - covariance[1][1]).abs() < 1e-15);
+        assert!((rotated[1][1] - covariance[0][0]).abs() < 1e-15);
+        assert!((rotated[0][1] + covariance[1][0]).abs() < 1e-15);
+        assert!((rotated[2][2] - covariance[2][2]).abs() < 1e-15);
+
+        // Errors propagated after the change of north are rotated the same way
+        borehole.propagate_errors(&errors);
+        let propagated = borehole.oriented_measurements[0].covariance.unwrap();
+        for (row, expected) in propagated.iter().zip(rotated) {
+            for (value, expected) in row.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-15);
+            }
+        }
+    }
+
+    #[test]
+    fn borehole_orient_matches_batch() {
+        let measurements = vec![
+            measurement(60.0, 65.0, Some(230.0)),
+            measurement(120.0, 65.0, Some(230.0)),
+        ];
+        let batch = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            measurements.clone(),
+            vec![station(None, 0.0), station(None, 100.0)],
+            vec![],
+        );
+        let streaming = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            vec![],
+            vec![station(None, 0.0), station(None, 100.0)],
+            vec![],
+        );
+
+        let oriented = streaming.orient(measurements[0].clone()).unwrap();
+        let (plane, expected) = (
+            oriented.plane.unwrap(),
+            batch.oriented_measurements[0].plane.unwrap(),
+        );
+        assert_eq!((plane.strike, plane.dip), (expected.strike, expected.dip));
+        assert_eq!(
+            streaming.orient(measurements[1].clone()).unwrap_err().depth,
+            batch.failed_measurements[0].depth
+        );
+    }
+
+    #[test]
+    fn borehole_check_survey() {
+        let mut stations = (0..5)
+            .map(|i| station(None, i as f64 * 30.0))
+            .collect::<Vec<BHOrientation>>();
+        stations[2].bearing = 280.0;
+        let mut borehole = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            vec![
+                measurement(10.0, 65.0, Some(230.0)),
+                measurement(55.0, 65.0, Some(230.0)),
+            ],
+            stations,
+            vec![],
+        );
+        assert!(borehole.oriented_measurements[1].survey_flags.is_empty());
+
+        borehole.check_survey(&SurveyQaLimits::default());
+        assert_eq!(borehole.survey_qa.len(), 5);
+        assert!(borehole.survey_qa[2]
+            .flags
+            .contains(&SurveyFlag::TrendOutlier));
+        assert!(borehole.oriented_measurements[0].survey_flags.is_empty());
+        assert_eq!(
+            borehole.oriented_measurements[1].survey_flags,
+            borehole.survey_qa[2].flags
+        );
+        let streamed = borehole
+            .orient(measurement(65.0, 65.0, Some(230.0)))
+            .unwrap();
+        assert_eq!(streamed.survey_flags, borehole.survey_qa[2].flags);
+    }
+
+    #[test]
+    fn borehole_single_survey_station() {
+        let borehole = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::NegativeDown,
+            BetaConvention::default(),
+            vec![measurement(250.0, 65.0, Some(230.0))],
+            vec![station(None, 0.0)],
+            vec![],
+        );
+
+        let plane = borehole.oriented_measurements[0].plane.unwrap();
+        assert_eq!(plane.pole.trend.round(), 286.0);
+    }
+
     #[test]
     fn real_world_orient() {
         // From measurements conducted on Loulo 3 brownfields drill core in 2015. See test_data
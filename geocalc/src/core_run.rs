@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::borehole::{BHOrientationLine, HoleRecord};
+
+/// How much the orientation mark on a drill run can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrientationConfidence {
+    Good,
+    Fair,
+    Poor,
+    /// The run has no usable orientation mark
+    #[serde(rename = "none", alias = "unoriented")]
+    Unoriented,
+}
+
+impl OrientationConfidence {
+    pub fn is_oriented(&self) -> bool {
+        !matches!(self, Self::Unoriented)
+    }
+}
+
+impl fmt::Display for OrientationConfidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Good => write!(f, "good"),
+            Self::Fair => write!(f, "fair"),
+            Self::Poor => write!(f, "poor"),
+            Self::Unoriented => write!(f, "none"),
+        }
+    }
+}
+
+/// A single drill run with its orientation mark.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CoreRun {
+    /// The hole the run belongs to, only needed when runs from several holes are mixed
+    #[serde(default)]
+    pub hole_id: Option<String>,
+    /// Depth at the start of the run
+    pub from: f64,
+    /// Depth at the end of the run
+    pub to: f64,
+    pub confidence: OrientationConfidence,
+    /// The orientation line marked on this run.
+    /// Falls back to the `Borehole` orientation line when not set.
+    #[serde(default)]
+    pub orientation_line: Option<BHOrientationLine>,
+}
+
+impl HoleRecord for CoreRun {
+    fn hole_id(&self) -> Option<&str> {
+        self.hole_id.as_deref()
+    }
+}
+
+impl CoreRun {
+    pub fn contains(&self, depth: f64) -> bool {
+        depth >= self.from && depth <= self.to
+    }
+}
+
+/// Find the run a depth falls in.
+/// Where two runs share a boundary depth the first run wins.
+pub fn run_at_depth(runs: &[CoreRun], depth: f64) -> Option<&CoreRun> {
+    runs.iter().find(|run| run.contains(depth))
+}
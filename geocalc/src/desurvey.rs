@@ -0,0 +1,168 @@
+use na::Vector3;
+use serde::Serialize;
+
+use crate::{borehole::BHOrientation, project::Collar, utils::vector::hole_axis};
+
+/// A point in the coordinate system of the collars, x is east, y is north and z is up.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Location {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// Returns the location at `depth` along a hole using the minimum curvature method.
+/// `stations` must be sorted by depth and use the `NegativeDown` inclination convention.
+/// The hole is extended in a straight line past the last station.
+pub fn desurvey(collar: &Collar, stations: &[BHOrientation], depth: f64) -> Location {
+    DesurveyedHole::new(collar, stations).location_at(depth)
+}
+
+/// The stations of a hole located from its collar once, so that many depths can be located
+/// without desurveying from the collar each time.
+#[derive(Debug, Clone)]
+pub(crate) struct DesurveyedHole {
+    stations: Vec<BHOrientation>,
+    positions: Vec<Vector3<f64>>,
+}
+
+impl DesurveyedHole {
+    /// `stations` must be sorted by depth and use the `NegativeDown` inclination convention.
+    pub(crate) fn new(collar: &Collar, stations: &[BHOrientation]) -> Self {
+        let mut position = Vector3::new(collar.x, collar.y, collar.z);
+        let mut positions = Vec::with_capacity(stations.len().max(1));
+        positions.push(position);
+        for pair in stations.windows(2) {
+            let start = direction(&pair[0]);
+            let end = direction(&pair[1]);
+            position += segment(
+                &start,
+                &end,
+                start.angle(&end),
+                pair[1].depth - pair[0].depth,
+            );
+            positions.push(position);
+        }
+        Self {
+            stations: stations.to_vec(),
+            positions,
+        }
+    }
+
+    /// Returns the location at `depth` by minimum curvature from the station above it,
+    /// the hole is extended in a straight line past the last station.
+    pub(crate) fn location_at(&self, depth: f64) -> Location {
+        // The last station above `depth`, depths at or above the first station are at the collar
+        let index = match self
+            .stations
+            .partition_point(|station| station.depth < depth)
+        {
+            0 => return to_location(self.positions[0]),
+            index => index - 1,
+        };
+        let previous = &self.stations[index];
+        let Some(station) = self.stations.get(index + 1) else {
+            return to_location(
+                self.positions[index] + direction(previous) * (depth - previous.depth),
+            );
+        };
+        if depth >= station.depth {
+            return to_location(self.positions[index + 1]);
+        }
+        let start = direction(previous);
+        let (end, dogleg) = slerp(
+            &start,
+            &direction(station),
+            (depth - previous.depth) / (station.depth - previous.depth),
+        );
+        to_location(self.positions[index] + segment(&start, &end, dogleg, depth - previous.depth))
+    }
+}
+
+pub(crate) fn direction(station: &BHOrientation) -> Vector3<f64> {
+    hole_axis(
+        station.bearing.to_radians(),
+        station.inclination.to_radians(),
+    )
+}
+
+/// Returns the direction `fraction` of the way along the arc from `start` to `end`
+/// and the angle from `start` to it.
+pub(crate) fn slerp(
+    start: &Vector3<f64>,
+    end: &Vector3<f64>,
+    fraction: f64,
+) -> (Vector3<f64>, f64) {
+    let dogleg = start.angle(end);
+    if dogleg < 1e-9 {
+        return (*start, 0.0);
+    }
+    let direction = (start * ((1.0 - fraction) * dogleg).sin() + end * (fraction * dogleg).sin())
+        / dogleg.sin();
+    (direction, fraction * dogleg)
+}
+
+/// The displacement along an arc of `length` between two directions `dogleg` radians apart
+fn segment(start: &Vector3<f64>, end: &Vector3<f64>, dogleg: f64, length: f64) -> Vector3<f64> {
+    let ratio_factor = if dogleg < 1e-9 {
+        1.0
+    } else {
+        2.0 / dogleg * (dogleg / 2.0).tan()
+    };
+    (start + end) * length / 2.0 * ratio_factor
+}
+
+fn to_location(position: Vector3<f64>) -> Location {
+    Location {
+        x: position.x,
+        y: position.y,
+        z: position.z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{project::AzimuthDatum, test_utils::station};
+
+    fn collar() -> Collar {
+        Collar {
+            hole_id: "DH1".to_string(),
+            x: 1000.0,
+            y: 2000.0,
+            z: 300.0,
+            eoh: 200.0,
+            date: None,
+            azimuth_datum: AzimuthDatum::True,
+            declination: None,
+            latitude: None,
+            longitude: None,
+        }
+    }
+
+    #[test]
+    fn desurvey_straight_hole() {
+        let stations = [station(0.0, 90.0, -30.0), station(100.0, 90.0, -30.0)];
+        let location = desurvey(&collar(), &stations, 150.0);
+
+        assert!((location.x - (1000.0 + 150.0 * 30f64.to_radians().cos())).abs() < 1e-9);
+        assert!((location.y - 2000.0).abs() < 1e-9);
+        assert!((location.z - (300.0 - 75.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn desurvey_curved_hole_follows_arc() {
+        // A quarter circle from vertical to horizontal east has a radius of 2L/π
+        let length = 100.0;
+        let radius = 2.0 * length / std::f64::consts::PI;
+        let stations = [station(0.0, 90.0, -90.0), station(length, 90.0, 0.0)];
+        let end = desurvey(&collar(), &stations, length);
+        assert!((end.x - (1000.0 + radius)).abs() < 1e-9);
+        assert!((end.z - (300.0 - radius)).abs() < 1e-9);
+
+        let middle = desurvey(&collar(), &stations, length / 2.0);
+        let expected = radius * (1.0 - std::f64::consts::FRAC_PI_4.cos());
+        assert!((middle.x - (1000.0 + expected)).abs() < 1e-9);
+        assert!((middle.z - (300.0 - radius * std::f64::consts::FRAC_PI_4.sin())).abs() < 1e-9);
+    }
+}
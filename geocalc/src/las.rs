@@ -0,0 +1,266 @@
+use crate::borehole::BHOrientation;
+
+/// The null value of a LAS file that does not set `NULL` in its well section
+const DEFAULT_NULL: f64 = -999.25;
+
+/// Curve mnemonics of the survey in a LAS file
+const DEPTH_CURVES: [&str; 3] = ["DEPT", "DEPTH", "MD"];
+const BEARING_CURVES: [&str; 3] = ["AZIM", "AZI", "AZIMUTH"];
+const INCLINATION_CURVES: [&str; 3] = ["INCL", "INC", "DEVI"];
+
+/// A deviation survey read from a LAS file
+#[derive(Debug, Clone)]
+pub struct LasSurvey {
+    /// The `WELL` of the well section, used as the hole id of the stations
+    pub well: Option<String>,
+    /// Depths in metres, bearings and inclinations in degrees as recorded
+    pub stations: Vec<BHOrientation>,
+    /// The number of depths skipped because a curve held the null value
+    pub null_rows: usize,
+}
+
+/// A header line `MNEM.UNIT DATA : DESCRIPTION`
+struct HeaderLine<'a> {
+    mnemonic: &'a str,
+    unit: &'a str,
+    data: &'a str,
+}
+
+impl<'a> HeaderLine<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (mnemonic, rest) = line.split_once('.')?;
+        // The unit follows the dot without a space, the description follows the last colon
+        let (unit, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let data = rest.rsplit_once(':').map_or(rest, |(data, _)| data);
+        Some(Self {
+            mnemonic: mnemonic.trim(),
+            unit: unit.trim(),
+            data: data.trim(),
+        })
+    }
+}
+
+/// The length of a depth unit in metres, depths without a unit are taken as metres
+fn depth_scale(unit: &str) -> Result<f64, String> {
+    match unit.to_uppercase().as_str() {
+        "" | "M" | "METER" | "METERS" | "METRE" | "METRES" => Ok(1.0),
+        "F" | "FT" | "FEET" | "FOOT" => Ok(0.3048),
+        unit => Err(format!("Unknown depth unit: {unit}")),
+    }
+}
+
+/// Converts an angle in `unit` to degrees, angles without a unit are taken as degrees
+fn angle_to_degrees(unit: &str) -> Result<fn(f64) -> f64, String> {
+    match unit.to_uppercase().as_str() {
+        "" | "DEG" | "DEGREE" | "DEGREES" => Ok(|angle| angle),
+        "RAD" | "RADIAN" | "RADIANS" => Ok(f64::to_degrees),
+        unit => Err(format!("Unknown angle unit: {unit}")),
+    }
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Version,
+    Well,
+    Curve,
+    Data,
+    Other,
+}
+
+/// Read a deviation survey from the text of a LAS 2.0 (or 1.2) file with depth, azimuth and
+/// inclination curves, e.g. `DEPT`, `AZIM` and `INCL`.
+/// Depths are converted to metres and angles to degrees by the units of the curves.
+/// The inclination convention is not recorded in LAS files, many tools report inclinations from
+/// the vertical, see `InclinationConvention::FromVertical`.
+/// Depths at which any of the three curves holds the `NULL` value are skipped.
+pub fn read_las_survey(text: &str) -> Result<LasSurvey, String> {
+    let mut section = Section::Other;
+    let mut well = None;
+    let mut null = DEFAULT_NULL;
+    let mut start_unit = "";
+    let mut curves: Vec<(&str, &str)> = vec![];
+    let mut rows: Vec<Vec<f64>> = vec![];
+
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+    {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('~') {
+            section = match name.chars().next().map(|c| c.to_ascii_uppercase()) {
+                Some('V') => Section::Version,
+                Some('W') => Section::Well,
+                Some('C') => Section::Curve,
+                Some('A') => Section::Data,
+                _ => Section::Other,
+            };
+            continue;
+        }
+        if section == Section::Data {
+            let values = line
+                .split_whitespace()
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| format!("Line {number}: invalid value in {line}"))?;
+            if values.len() != curves.len() {
+                return Err(format!(
+                    "Line {number}: expected {} values, found {}",
+                    curves.len(),
+                    values.len()
+                ));
+            }
+            rows.push(values);
+            continue;
+        }
+        let Some(header) = HeaderLine::parse(line) else {
+            continue;
+        };
+        match (&section, header.mnemonic.to_uppercase().as_str()) {
+            (Section::Version, "VERS") if header.data.starts_with('3') => {
+                return Err("LAS 3.0 files are not supported".to_string())
+            }
+            (Section::Version, "WRAP") if header.data.eq_ignore_ascii_case("YES") => {
+                return Err("Wrapped LAS files are not supported".to_string())
+            }
+            (Section::Well, "WELL") if !header.data.is_empty() => {
+                well = Some(header.data.to_string())
+            }
+            (Section::Well, "NULL") => {
+                null = header
+                    .data
+                    .parse()
+                    .map_err(|_| format!("Line {number}: invalid null value {}", header.data))?
+            }
+            (Section::Well, "STRT") => start_unit = header.unit,
+            (Section::Curve, _) => curves.push((header.mnemonic, header.unit)),
+            _ => (),
+        }
+    }
+
+    let curve = |names: [&str; 3]| {
+        curves
+            .iter()
+            .position(|(mnemonic, _)| names.iter().any(|name| mnemonic.eq_ignore_ascii_case(name)))
+            .ok_or_else(|| format!("The file has no {} curve", names[0]))
+    };
+    let (depth, bearing, inclination) = (
+        curve(DEPTH_CURVES)?,
+        curve(BEARING_CURVES)?,
+        curve(INCLINATION_CURVES)?,
+    );
+    let depth_unit = match curves[depth].1 {
+        "" => start_unit,
+        unit => unit,
+    };
+    let depth_scale = depth_scale(depth_unit)?;
+    let bearing_to_degrees = angle_to_degrees(curves[bearing].1)?;
+    let inclination_to_degrees = angle_to_degrees(curves[inclination].1)?;
+
+    let mut null_rows = 0;
+    let mut stations = vec![];
+    for values in rows {
+        let (depth, bearing, inclination) = (values[depth], values[bearing], values[inclination]);
+        if [depth, bearing, inclination].contains(&null) {
+            null_rows += 1;
+            continue;
+        }
+        stations.push(BHOrientation {
+            hole_id: well.clone(),
+            survey_type: None,
+            depth: depth * depth_scale,
+            bearing: bearing_to_degrees(bearing),
+            inclination: inclination_to_degrees(inclination),
+        });
+    }
+    if stations.is_empty() {
+        return Err("The file has no survey stations".to_string());
+    }
+    Ok(LasSurvey {
+        well,
+        stations,
+        null_rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SURVEY: &str = "~Version information
+ VERS.                 2.0 : CWLS LOG ASCII STANDARD - VERSION 2.0
+ WRAP.                  NO : ONE LINE PER DEPTH STEP
+~Well information
+ STRT.F              0.000 : START DEPTH
+ STOP.F            300.000 : STOP DEPTH
+ STEP.F              0.000 : STEP
+ NULL.             -999.25 : NULL VALUE
+ WELL.              DH-007 : WELL
+ DATE.          2024-03-01 13:45 : LOG DATE
+~Curve information
+ DEPT.F                    : MEASURED DEPTH
+ GR  .GAPI                 : GAMMA RAY
+ AZIM.DEG                  : AZIMUTH
+ INCL.DEG                  : INCLINATION
+~Parameter information
+ BHT .DEGC          35.500 : BOTTOM HOLE TEMPERATURE
+~A  DEPT     GR       AZIM     INCL
+# Comments are allowed in the data
+    0.000   45.0    120.50   30.00
+  100.000   52.1    121.00 -999.25
+  200.000   48.7    121.75   31.50
+  300.000   50.2    122.00   32.25
+";
+
+    #[test]
+    fn read_las_survey_units_and_nulls() {
+        let survey = read_las_survey(SURVEY).unwrap();
+        assert_eq!(survey.well.as_deref(), Some("DH-007"));
+        assert_eq!(survey.null_rows, 1);
+        let stations = survey
+            .stations
+            .iter()
+            .map(|station| (station.depth, station.bearing, station.inclination))
+            .collect::<Vec<(f64, f64, f64)>>();
+        assert_eq!(
+            stations,
+            vec![
+                (0.0, 120.5, 30.0),
+                (200.0 * 0.3048, 121.75, 31.5),
+                (300.0 * 0.3048, 122.0, 32.25)
+            ]
+        );
+        assert!(survey
+            .stations
+            .iter()
+            .all(|station| station.hole_id.as_deref() == Some("DH-007")));
+    }
+
+    #[test]
+    fn read_las_survey_errors() {
+        let radians = SURVEY
+            .replace("AZIM.DEG", "AZIM.RAD")
+            .replace("120.50", "0.5");
+        let survey = read_las_survey(&radians).unwrap();
+        assert!((survey.stations[0].bearing - 0.5f64.to_degrees()).abs() < 1e-12);
+
+        assert!(read_las_survey(&SURVEY.replace("INCL.DEG", "TILT.DEG"))
+            .unwrap_err()
+            .contains("INCL"));
+        assert!(read_las_survey(&SURVEY.replace("AZIM.DEG", "AZIM.GON"))
+            .unwrap_err()
+            .contains("GON"));
+        assert!(
+            read_las_survey(&SURVEY.replace("WRAP.                  NO", "WRAP. YES"))
+                .unwrap_err()
+                .contains("Wrapped")
+        );
+        assert_eq!(
+            read_las_survey(&SURVEY.replace("48.7    121.75", "48.7")).unwrap_err(),
+            "Line 22: expected 4 values, found 3"
+        );
+    }
+}
@@ -1,9 +1,49 @@
 extern crate nalgebra as na;
 
 mod borehole;
+mod core_run;
+mod desurvey;
+mod las;
+mod local_grid;
+mod magnetic;
+mod project;
+mod projection;
 mod structure;
+mod survey_merge;
+mod survey_qa;
+mod survey_smoothing;
+#[cfg(test)]
+mod test_utils;
+mod uncertainty;
+mod unoriented;
 mod utils;
 mod validation;
 
-pub use crate::borehole::{BHOrientation, BHOrientationLine, Borehole, RawMeasurement};
-pub use crate::structure::Plane;
+pub use crate::borehole::{
+    group_by_hole, BHOrientation, BHOrientationLine, BetaApex, BetaConvention, BetaDirection,
+    Borehole, HoleRecord, InclinationConvention, MeasurementFailure, Orient, OrientedMeasurement,
+    RawMeasurement,
+};
+pub use crate::core_run::{CoreRun, OrientationConfidence};
+pub use crate::desurvey::{desurvey, Location};
+pub use crate::las::{read_las_survey, LasSurvey};
+pub use crate::local_grid::{ControlPoint, GridTransform, GridTransformKind, Residual};
+pub use crate::magnetic::{decimal_year, MagneticField, MagneticModel};
+pub use crate::project::{
+    AzimuthDatum, AzimuthReference, Collar, IntegrityIssue, Interval, Project,
+};
+pub use crate::projection::{
+    geodetic_from_utm, grid_convergence, utm_from_geodetic, UtmPoint, UtmZone,
+};
+pub use crate::structure::{Lineation, Plane};
+pub use crate::survey_merge::{
+    merge_surveys, MergedSurvey, SurveyDisagreement, DEFAULT_SURVEY_PRIORITY,
+};
+pub use crate::survey_qa::{
+    survey_qa, StationQa, SurveyFlag, SurveyQaLimits, DOGLEG_COURSE_LENGTH,
+};
+pub use crate::survey_smoothing::{smooth_survey, SmoothedStation, SurveySmoothing};
+pub use crate::uncertainty::{
+    ErrorEllipse, MeasurementErrors, OrientInput, PoleCovariance, PoleUncertainty, Sensitivities,
+};
+pub use crate::unoriented::{solve_alpha_cones, AlphaCone, ConeSolution};
@@ -0,0 +1,303 @@
+use na::{Matrix2, Vector2};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::normalise_azimuth;
+
+/// How a local grid is fitted to control points
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GridTransformKind {
+    /// Rotation, uniform scale and translation (Helmert), needs at least 2 control points
+    #[default]
+    Similarity,
+    /// Rotation, scale in two directions, shear and translation, needs at least 3 control points
+    Affine,
+}
+
+/// A point known in both the local grid and the map grid (e.g. UTM)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ControlPoint {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub local_x: f64,
+    pub local_y: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// The misfit of a control point after fitting, in map units
+#[derive(Debug, Clone, Serialize)]
+pub struct Residual {
+    pub name: Option<String>,
+    pub dx: f64,
+    pub dy: f64,
+    pub distance: f64,
+}
+
+/// A 2D transform from a local mine grid to a map grid such as UTM.
+/// Elevations are not changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridTransform {
+    /// map = matrix * local + translation
+    matrix: Matrix2<f64>,
+    translation: Vector2<f64>,
+}
+
+impl GridTransform {
+    /// A similarity transform from explicit parameters.
+    /// `rotation` is the map bearing of local grid north in degrees, `scale` the map length of a local unit
+    /// and the two origins the same point in local and map coordinates.
+    pub fn similarity(
+        rotation: f64,
+        scale: f64,
+        local_origin: (f64, f64),
+        map_origin: (f64, f64),
+    ) -> Result<Self, String> {
+        if scale.is_nan() || scale <= 0.0 {
+            return Err(format!(
+                "The scale of a grid transform must be positive, found {scale}"
+            ));
+        }
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let matrix = Matrix2::new(cos, sin, -sin, cos) * scale;
+        let translation = Vector2::new(map_origin.0, map_origin.1)
+            - matrix * Vector2::new(local_origin.0, local_origin.1);
+        Ok(Self {
+            matrix,
+            translation,
+        })
+    }
+
+    /// Fit the transform to control points by least squares, returns the residual of every point.
+    pub fn fit(
+        points: &[ControlPoint],
+        kind: GridTransformKind,
+    ) -> Result<(Self, Vec<Residual>), String> {
+        let needed = match kind {
+            GridTransformKind::Similarity => 2,
+            GridTransformKind::Affine => 3,
+        };
+        if points.len() < needed {
+            return Err(format!(
+                "A {kind:?} grid transform needs at least {needed} control points, found {}",
+                points.len()
+            )
+            .to_lowercase());
+        }
+        // Centre the coordinates, map coordinates are too large to square accurately
+        let count = points.len() as f64;
+        let local_centre = points
+            .iter()
+            .map(|point| Vector2::new(point.local_x, point.local_y))
+            .sum::<Vector2<f64>>()
+            / count;
+        let map_centre = points
+            .iter()
+            .map(|point| Vector2::new(point.x, point.y))
+            .sum::<Vector2<f64>>()
+            / count;
+        let centred = points
+            .iter()
+            .map(|point| {
+                (
+                    Vector2::new(point.local_x, point.local_y) - local_centre,
+                    Vector2::new(point.x, point.y) - map_centre,
+                )
+            })
+            .collect::<Vec<(Vector2<f64>, Vector2<f64>)>>();
+        let spread = centred
+            .iter()
+            .map(|(local, _)| local.norm_squared())
+            .sum::<f64>();
+        if spread == 0.0 {
+            return Err("The control points are all at the same local position".to_string());
+        }
+
+        let matrix = match kind {
+            GridTransformKind::Similarity => {
+                let (mut cos, mut sin) = (0.0, 0.0);
+                for (local, map) in centred.iter() {
+                    cos += local.x * map.x + local.y * map.y;
+                    sin += local.y * map.x - local.x * map.y;
+                }
+                Matrix2::new(cos, sin, -sin, cos) / spread
+            }
+            GridTransformKind::Affine => {
+                let (mut local_local, mut map_local) = (Matrix2::zeros(), Matrix2::zeros());
+                for (local, map) in centred.iter() {
+                    local_local += local * local.transpose();
+                    map_local += map * local.transpose();
+                }
+                // Collinear control points leave the transform across the line undefined
+                if local_local.determinant() < 1e-12 * spread * spread {
+                    return Err("The control points are collinear".to_string());
+                }
+                map_local * local_local.try_inverse().unwrap()
+            }
+        };
+        let transform = Self {
+            matrix,
+            translation: map_centre - matrix * local_centre,
+        };
+
+        let residuals = points
+            .iter()
+            .map(|point| {
+                let (x, y) = transform.to_map(point.local_x, point.local_y);
+                let (dx, dy) = (point.x - x, point.y - y);
+                Residual {
+                    name: point.name.clone(),
+                    dx,
+                    dy,
+                    distance: dx.hypot(dy),
+                }
+            })
+            .collect();
+        Ok((transform, residuals))
+    }
+
+    /// Convert local grid coordinates to map coordinates
+    pub fn to_map(&self, x: f64, y: f64) -> (f64, f64) {
+        let map = self.matrix * Vector2::new(x, y) + self.translation;
+        (map.x, map.y)
+    }
+
+    /// Convert map coordinates to local grid coordinates
+    pub fn to_local(&self, x: f64, y: f64) -> (f64, f64) {
+        // Fitted and explicit transforms always have a positive determinant
+        let local = self.matrix.try_inverse().unwrap() * (Vector2::new(x, y) - self.translation);
+        (local.x, local.y)
+    }
+
+    /// The map bearing of local grid north in degrees, i.e. the angle added to local azimuths to refer
+    /// them to map grid north. For affine transforms this is the rotation of the closest similarity.
+    pub fn rotation(&self) -> f64 {
+        let m = self.matrix;
+        (m[(0, 1)] - m[(1, 0)])
+            .atan2(m[(0, 0)] + m[(1, 1)])
+            .to_degrees()
+    }
+
+    /// The mean map length of a local unit length
+    pub fn scale(&self) -> f64 {
+        self.matrix.determinant().sqrt()
+    }
+
+    /// Refer a local grid azimuth to map grid north
+    pub fn azimuth_to_map(&self, azimuth: f64) -> f64 {
+        normalise_azimuth(azimuth + self.rotation())
+    }
+
+    /// Refer a map grid azimuth to local grid north
+    pub fn azimuth_to_local(&self, azimuth: f64) -> f64 {
+        normalise_azimuth(azimuth - self.rotation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control_points(transform: &GridTransform) -> Vec<ControlPoint> {
+        [(0.0, 0.0), (1000.0, 0.0), (0.0, 2000.0), (1500.0, 1800.0)]
+            .iter()
+            .enumerate()
+            .map(|(index, &(local_x, local_y))| {
+                let (x, y) = transform.to_map(local_x, local_y);
+                ControlPoint {
+                    name: Some(format!("CP{index}")),
+                    local_x,
+                    local_y,
+                    x,
+                    y,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grid_similarity() {
+        let transform =
+            GridTransform::similarity(30.0, 1.0, (10_000.0, 5_000.0), (650_000.0, 5_800_000.0))
+                .unwrap();
+        assert_eq!(
+            transform.to_map(10_000.0, 5_000.0),
+            (650_000.0, 5_800_000.0)
+        );
+        // Local north is 30° east of map north
+        let (x, y) = transform.to_map(10_000.0, 5_100.0);
+        assert!((x - 650_050.0).abs() < 1e-6);
+        assert!((y - (5_800_000.0 + 100.0 * 30f64.to_radians().cos())).abs() < 1e-6);
+        let (local_x, local_y) = transform.to_local(x, y);
+        assert!((local_x - 10_000.0).abs() < 1e-6 && (local_y - 5_100.0).abs() < 1e-6);
+
+        assert!((transform.rotation() - 30.0).abs() < 1e-12);
+        assert!((transform.scale() - 1.0).abs() < 1e-12);
+        assert!((transform.azimuth_to_map(340.0) - 10.0).abs() < 1e-9);
+        assert!((transform.azimuth_to_local(10.0) - 340.0).abs() < 1e-9);
+        assert!(GridTransform::similarity(30.0, 0.0, (0.0, 0.0), (0.0, 0.0)).is_err());
+    }
+
+    #[test]
+    fn grid_fit_similarity() {
+        let expected =
+            GridTransform::similarity(-12.5, 0.9996, (0.0, 0.0), (402_000.0, 7_600_000.0)).unwrap();
+        let mut points = control_points(&expected);
+        let (transform, residuals) =
+            GridTransform::fit(&points, GridTransformKind::Similarity).unwrap();
+        assert!((transform.rotation() + 12.5).abs() < 1e-9);
+        assert!((transform.scale() - 0.9996).abs() < 1e-12);
+        assert!(residuals.iter().all(|residual| residual.distance < 1e-6));
+
+        // A surveying error shows up in the residuals
+        points[3].x += 0.4;
+        let (_, residuals) = GridTransform::fit(&points, GridTransformKind::Similarity).unwrap();
+        assert_eq!(residuals[3].name.as_deref(), Some("CP3"));
+        assert!(residuals[3].dx > 0.1);
+        assert!(residuals[..3]
+            .iter()
+            .all(|residual| residual.distance < residuals[3].distance));
+        assert!(
+            residuals
+                .iter()
+                .map(|residual| residual.dx)
+                .sum::<f64>()
+                .abs()
+                < 1e-6
+        );
+
+        assert!(GridTransform::fit(&points[..1], GridTransformKind::Similarity).is_err());
+    }
+
+    #[test]
+    fn grid_fit_affine() {
+        let similarity =
+            GridTransform::similarity(45.0, 1.0, (0.0, 0.0), (500_000.0, 6_000_000.0)).unwrap();
+        // Stretch the local x axis
+        let expected = GridTransform {
+            matrix: similarity.matrix * Matrix2::new(1.01, 0.0, 0.0, 1.0),
+            ..similarity
+        };
+        let points = control_points(&expected);
+        let (transform, residuals) =
+            GridTransform::fit(&points, GridTransformKind::Affine).unwrap();
+        assert!((transform.matrix - expected.matrix).abs().max() < 1e-9);
+        assert!(residuals.iter().all(|residual| residual.distance < 1e-6));
+        // The similarity cannot take up the stretch
+        let (_, residuals) = GridTransform::fit(&points, GridTransformKind::Similarity).unwrap();
+        assert!(residuals.iter().any(|residual| residual.distance > 1.0));
+
+        assert!(GridTransform::fit(&points[..2], GridTransformKind::Affine).is_err());
+        let collinear = [(0.0, 0.0), (10.0, 10.0), (20.0, 20.0)]
+            .iter()
+            .map(|&(local_x, local_y)| ControlPoint {
+                name: None,
+                local_x,
+                local_y,
+                x: local_x,
+                y: local_y,
+            })
+            .collect::<Vec<ControlPoint>>();
+        assert!(GridTransform::fit(&collinear, GridTransformKind::Affine).is_err());
+    }
+}
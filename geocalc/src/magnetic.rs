@@ -0,0 +1,403 @@
+use serde::Serialize;
+
+/// WGS84 semi-major axis in km
+const WGS84_A: f64 = 6378.137;
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// A spherical harmonic model of the main geomagnetic field in the coefficient layout of the
+/// World Magnetic Model (WMM).
+///
+/// The WMM2025 is bundled, see `wmm`, other models can be loaded from their `.COF` file, see `from_cof`.
+#[derive(Debug, Clone)]
+pub struct MagneticModel {
+    pub name: String,
+    /// The decimal year the coefficients are valid at
+    pub epoch: f64,
+    /// The reference radius in km
+    pub radius: f64,
+    degree: usize,
+    /// Gauss coefficients in nT indexed by `index(n, m)`
+    g: Vec<f64>,
+    h: Vec<f64>,
+    /// Secular variation of the coefficients in nT per year
+    dg: Vec<f64>,
+    dh: Vec<f64>,
+}
+
+/// The magnetic field vector in nT
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MagneticField {
+    pub north: f64,
+    pub east: f64,
+    pub down: f64,
+}
+
+impl MagneticField {
+    /// The angle from true north to the horizontal field in degrees, positive to the east
+    pub fn declination(&self) -> f64 {
+        self.east.atan2(self.north).to_degrees()
+    }
+
+    /// The angle of the field below the horizontal in degrees
+    pub fn inclination(&self) -> f64 {
+        self.down
+            .atan2((self.north.powi(2) + self.east.powi(2)).sqrt())
+            .to_degrees()
+    }
+}
+
+fn index(n: usize, m: usize) -> usize {
+    n * (n + 1) / 2 + m
+}
+
+/// The coefficient file of the WMM2025 published by NOAA, valid from 2025.0 to 2030.0
+const WMM_COF: &str = include_str!("../data/WMM.COF");
+
+impl MagneticModel {
+    /// The bundled World Magnetic Model, WMM2025
+    pub fn wmm() -> Self {
+        Self::from_cof(WMM_COF).expect("The bundled WMM coefficient file is valid")
+    }
+
+    /// Parse a coefficient file in the NOAA `.COF` format used for the WMM, e.g.
+    /// ```text
+    ///     2020.0            WMM-2020        12/10/2019
+    ///   1  0  -29404.5       0.0        6.7        0.0
+    ///   1  1   -1450.7    4652.9        7.7      -25.1
+    /// 999999999999999999999999999999999999999999999999
+    /// ```
+    /// The header holds the epoch and the name, each following line holds
+    /// n, m, g, h, dg/dt and dh/dt. The file ends at a line of nines.
+    pub fn from_cof(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().ok_or("The coefficient file is empty")?;
+        let mut header = header.split_whitespace();
+        let epoch = header
+            .next()
+            .and_then(|epoch| epoch.parse::<f64>().ok())
+            .ok_or("The coefficient file header has no epoch")?;
+        let name = header.next().unwrap_or("").to_string();
+
+        let mut rows = vec![];
+        for line in lines {
+            if line.trim_start().starts_with("9999") {
+                break;
+            }
+            let values = line
+                .split_whitespace()
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| format!("Invalid coefficient line: {line}"))?;
+            if values.len() < 6 {
+                return Err(format!("Invalid coefficient line: {line}"));
+            }
+            let (n, m) = (values[0] as usize, values[1] as usize);
+            if n == 0 || m > n {
+                return Err(format!("Invalid degree and order: {line}"));
+            }
+            rows.push((n, m, values[2], values[3], values[4], values[5]));
+        }
+        let degree = rows
+            .iter()
+            .map(|row| row.0)
+            .max()
+            .ok_or("The coefficient file has no coefficients")?;
+
+        let size = index(degree, degree) + 1;
+        let mut model = Self {
+            name,
+            epoch,
+            radius: 6371.2,
+            degree,
+            g: vec![0.0; size],
+            h: vec![0.0; size],
+            dg: vec![0.0; size],
+            dh: vec![0.0; size],
+        };
+        for (n, m, g, h, dg, dh) in rows {
+            let i = index(n, m);
+            (model.g[i], model.h[i], model.dg[i], model.dh[i]) = (g, h, dg, dh);
+        }
+        Ok(model)
+    }
+
+    /// Returns true when `decimal_year` is within the five years a model is valid for
+    pub fn is_valid_at(&self, decimal_year: f64) -> bool {
+        (self.epoch..=self.epoch + 5.0).contains(&decimal_year)
+    }
+
+    /// The field at a geodetic `latitude` and `longitude` (degrees) and `height` above the
+    /// WGS84 ellipsoid (km) at `decimal_year`, in the geodetic north, east and down directions.
+    pub fn field(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        height: f64,
+        decimal_year: f64,
+    ) -> MagneticField {
+        let dt = decimal_year - self.epoch;
+        let latitude = latitude.to_radians();
+        let longitude = longitude.to_radians();
+
+        // Geodetic to geocentric spherical coordinates
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let prime_vertical = WGS84_A / (1.0 - e2 * latitude.sin().powi(2)).sqrt();
+        let p = (prime_vertical + height) * latitude.cos();
+        let z = (prime_vertical * (1.0 - e2) + height) * latitude.sin();
+        let r = (p * p + z * z).sqrt();
+        let geocentric_latitude = (z / r).asin();
+        // Colatitude, kept away from the poles where the east component is undefined
+        let theta = (std::f64::consts::FRAC_PI_2 - geocentric_latitude)
+            .clamp(1e-10, std::f64::consts::PI - 1e-10);
+
+        let (p_nm, dp_nm) = schmidt_legendre(self.degree, theta);
+        let (mut b_r, mut b_theta, mut b_phi) = (0.0, 0.0, 0.0);
+        for n in 1..=self.degree {
+            let ratio = (self.radius / r).powi(n as i32 + 2);
+            for m in 0..=n {
+                let i = index(n, m);
+                let g = self.g[i] + dt * self.dg[i];
+                let h = self.h[i] + dt * self.dh[i];
+                let (sin, cos) = (m as f64 * longitude).sin_cos();
+                b_r += (n + 1) as f64 * ratio * (g * cos + h * sin) * p_nm[i];
+                b_theta -= ratio * (g * cos + h * sin) * dp_nm[i];
+                b_phi -= ratio * m as f64 * (-g * sin + h * cos) * p_nm[i] / theta.sin();
+            }
+        }
+
+        // Rotate from geocentric to geodetic north and down
+        let (north, down) = (-b_theta, -b_r);
+        let psi = geocentric_latitude - latitude;
+        MagneticField {
+            north: north * psi.cos() - down * psi.sin(),
+            east: b_phi,
+            down: north * psi.sin() + down * psi.cos(),
+        }
+    }
+
+    /// The declination in degrees, positive to the east, see `field`
+    pub fn declination(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        height: f64,
+        decimal_year: f64,
+    ) -> f64 {
+        self.field(latitude, longitude, height, decimal_year)
+            .declination()
+    }
+}
+
+/// Schmidt semi-normalised associated Legendre functions of cos(theta) and their derivatives
+/// with respect to the colatitude theta, indexed by `index(n, m)`
+fn schmidt_legendre(degree: usize, theta: f64) -> (Vec<f64>, Vec<f64>) {
+    let size = index(degree, degree) + 1;
+    let (sin, cos) = theta.sin_cos();
+    let mut p = vec![0.0; size];
+    let mut dp = vec![0.0; size];
+    p[0] = 1.0;
+
+    // Gauss normalised recursion
+    for n in 1..=degree {
+        for m in 0..=n {
+            let i = index(n, m);
+            if n == m {
+                let j = index(n - 1, m - 1);
+                p[i] = sin * p[j];
+                dp[i] = sin * dp[j] + cos * p[j];
+            } else {
+                let j = index(n - 1, m);
+                let (p2, dp2, k) = match n {
+                    1 => (0.0, 0.0, 0.0),
+                    _ if m > n - 2 => (0.0, 0.0, 0.0),
+                    _ => (
+                        p[index(n - 2, m)],
+                        dp[index(n - 2, m)],
+                        (((n - 1) * (n - 1)) as f64 - (m * m) as f64)
+                            / (((2 * n - 1) * (2 * n - 3)) as f64),
+                    ),
+                };
+                p[i] = cos * p[j] - k * p2;
+                dp[i] = cos * dp[j] - sin * p[j] - k * dp2;
+            }
+        }
+    }
+
+    // Convert to Schmidt semi-normalisation
+    let mut schmidt = vec![0.0; size];
+    schmidt[0] = 1.0;
+    for n in 1..=degree {
+        schmidt[index(n, 0)] = schmidt[index(n - 1, 0)] * (2 * n - 1) as f64 / n as f64;
+        for m in 1..=n {
+            let delta = if m == 1 { 2.0 } else { 1.0 };
+            schmidt[index(n, m)] =
+                schmidt[index(n, m - 1)] * ((n - m + 1) as f64 * delta / (n + m) as f64).sqrt();
+        }
+    }
+    for i in 0..size {
+        p[i] *= schmidt[i];
+        dp[i] *= schmidt[i];
+    }
+    (p, dp)
+}
+
+/// Convert a date such as `2023-02-14` to a decimal year, a time after the date is ignored
+pub fn decimal_year(date: &str) -> Result<f64, String> {
+    let invalid = || format!("Invalid date {date}, expected YYYY-MM-DD");
+    let trimmed = date.trim();
+    let mut parts = trimmed.get(..10).unwrap_or(trimmed).split('-');
+    let mut next = || {
+        parts
+            .next()
+            .and_then(|part| part.parse::<u32>().ok())
+            .ok_or_else(invalid)
+    };
+    let (year, month, day) = (next()?, next()?, next()?);
+
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = [
+        31,
+        if leap { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month[month as usize - 1] {
+        return Err(invalid());
+    }
+    let day_of_year = days_in_month[..month as usize - 1].iter().sum::<u32>() + day;
+    let days_in_year = if leap { 366.0 } else { 365.0 };
+    Ok(year as f64 + (day_of_year as f64 - 1.0) / days_in_year)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A made up tilted dipole, not a real model
+    const DIPOLE: &str = "
+    2020.0            TEST-DIPOLE        01/01/2020
+  1  0  -30000.0       0.0       10.0        0.0
+  1  1       0.0    5000.0        0.0      100.0
+999999999999999999999999999999999999999999999999
+";
+
+    #[test]
+    fn magnetic_model_dipole_declination() {
+        let model = MagneticModel::from_cof(DIPOLE).unwrap();
+        assert_eq!(model.name, "TEST-DIPOLE");
+
+        // On the equator at the prime meridian north = -g10 and east = -h11
+        let expected = (-5000f64).atan2(30000.0).to_degrees();
+        assert!((model.declination(0.0, 0.0, 0.0, 2020.0) - expected).abs() < 1e-9);
+
+        // Secular variation
+        let expected = (-5500f64).atan2(29950.0).to_degrees();
+        assert!((model.declination(0.0, 0.0, 0.0, 2025.0) - expected).abs() < 1e-9);
+        assert!(!model.is_valid_at(2026.0));
+    }
+
+    #[test]
+    fn magnetic_model_axial_dipole_points_north() {
+        let model =
+            MagneticModel::from_cof("2020.0 AXIAL\n  1  0  -30000.0  0.0  0.0  0.0\n999999999\n")
+                .unwrap();
+        for (latitude, longitude) in [(-35.0, 150.0), (60.0, -100.0), (10.0, 20.0)] {
+            let field = model.field(latitude, longitude, 0.5, 2021.0);
+            assert!(field.declination().abs() < 1e-9);
+            // The field points down in the northern hemisphere
+            assert_eq!(field.inclination() > 0.0, latitude > 0.0);
+        }
+    }
+
+    #[test]
+    fn schmidt_legendre_closed_form() {
+        let theta = 0.7f64;
+        let (sin, cos) = theta.sin_cos();
+        let (p, dp) = schmidt_legendre(3, theta);
+        let expected = [
+            (index(2, 0), (3.0 * cos * cos - 1.0) / 2.0),
+            (index(2, 1), 3f64.sqrt() * cos * sin),
+            (index(2, 2), 3f64.sqrt() / 2.0 * sin * sin),
+            (index(3, 3), (5f64 / 8.0).sqrt() * sin.powi(3)),
+        ];
+        for (i, value) in expected {
+            assert!((p[i] - value).abs() < 1e-12);
+        }
+
+        let step = 1e-6;
+        let (above, _) = schmidt_legendre(3, theta + step);
+        let (below, _) = schmidt_legendre(3, theta - step);
+        for i in 0..p.len() {
+            assert!((dp[i] - (above[i] - below[i]) / (2.0 * step)).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn magnetic_model_bundled_wmm() {
+        let model = MagneticModel::wmm();
+        assert_eq!(model.name, "WMM-2025");
+        assert_eq!((model.epoch, model.degree), (2025.0, 12));
+        assert!(model.is_valid_at(2029.9));
+        assert!(!model.is_valid_at(2024.9));
+
+        // The declinations of the bundled coefficients at the locations of the NOAA test values,
+        // so that a change to the file is noticed
+        for (year, height, latitude, longitude, declination) in [
+            (2025.0, 0.0, 80.0, 0.0, 1.28),
+            (2025.0, 0.0, 0.0, 120.0, -0.16),
+            (2025.0, 0.0, -80.0, 240.0, 68.78),
+            (2027.5, 100.0, 80.0, 0.0, 2.16),
+            (2027.5, 100.0, 0.0, 120.0, -0.23),
+            (2027.5, 100.0, -80.0, 240.0, 67.93),
+        ] {
+            let result = model.declination(latitude, longitude, height, year);
+            assert!(
+                (result - declination).abs() < 0.01,
+                "{latitude} {longitude}: {result}"
+            );
+        }
+    }
+
+    #[test]
+    fn magnetic_model_wmm2020_test_values() {
+        // The test values published by NOAA with WMM2020 check the field synthesis
+        let model = MagneticModel::from_cof(include_str!("../data/WMM2020.COF")).unwrap();
+        for (latitude, longitude, north, east, down, declination, inclination) in [
+            (80.0, 0.0, 6570.4, -146.3, 54606.0, -1.28, 83.14),
+            (0.0, 120.0, 39624.3, 109.9, -10932.5, 0.16, -15.42),
+            (-80.0, 240.0, 5940.6, 15772.1, -52480.8, 69.36, -72.20),
+        ] {
+            let field = model.field(latitude, longitude, 0.0, 2020.0);
+            assert!((field.north - north).abs() < 0.1);
+            assert!((field.east - east).abs() < 0.1);
+            assert!((field.down - down).abs() < 0.1);
+            assert!((field.declination() - declination).abs() < 0.01);
+            assert!((field.inclination() - inclination).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn magnetic_model_invalid_file() {
+        assert!(MagneticModel::from_cof("").is_err());
+        assert!(MagneticModel::from_cof("2020.0 WMM\n1 2 0 0 0 0\n").is_err());
+        assert!(MagneticModel::from_cof("2020.0 WMM\n999999\n").is_err());
+    }
+
+    #[test]
+    fn decimal_years() {
+        assert_eq!(decimal_year("2020-01-01").unwrap(), 2020.0);
+        assert_eq!(decimal_year("2021-07-02").unwrap(), 2021.0 + 182.0 / 365.0);
+        assert!(decimal_year("2021-02-29").is_err());
+        assert!(decimal_year("14/02/2023").is_err());
+        assert_eq!(decimal_year("2020-01-01T09:30").unwrap(), 2020.0);
+    }
+}
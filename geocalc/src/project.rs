@@ -0,0 +1,974 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    borehole::{
+        group_by_hole, validate_survey, BHOrientation, BHOrientationLine, BetaConvention, Borehole,
+        HoleRecord, InclinationConvention, RawMeasurement,
+    },
+    core_run::CoreRun,
+    local_grid::GridTransform,
+    magnetic::{decimal_year, MagneticModel},
+    projection::{geodetic_from_utm, grid_convergence, UtmZone},
+    survey_merge::merge_surveys,
+    survey_smoothing::{smooth_survey, SurveySmoothing},
+    utils::normalise_azimuth,
+};
+
+/// The north reference that azimuths are measured from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AzimuthDatum {
+    #[default]
+    True,
+    Magnetic,
+    Grid,
+    /// A local mine grid, see `Project::local_grid`
+    Local,
+}
+
+impl fmt::Display for AzimuthDatum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::True => write!(f, "true"),
+            Self::Magnetic => write!(f, "magnetic"),
+            Self::Grid => write!(f, "grid"),
+            Self::Local => write!(f, "local"),
+        }
+    }
+}
+
+/// The collar (start) of a drill hole.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Collar {
+    pub hole_id: String,
+    /// Easting of the collar
+    pub x: f64,
+    /// Northing of the collar
+    pub y: f64,
+    /// Elevation of the collar
+    pub z: f64,
+    /// The end of hole depth
+    pub eoh: f64,
+    /// The date the hole was drilled or surveyed, e.g. 2023-02-14
+    #[serde(default)]
+    pub date: Option<String>,
+    /// The north reference of the survey bearings of the hole
+    #[serde(default)]
+    pub azimuth_datum: AzimuthDatum,
+    /// The magnetic declination in degrees (positive east) for magnetic survey bearings.
+    /// When not set it is computed from the `MagneticModel` of the project.
+    #[serde(default)]
+    pub declination: Option<f64>,
+    /// The geodetic latitude of the collar in degrees, used for the magnetic model
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    /// The geodetic longitude of the collar in degrees, used for the magnetic model
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+/// How the survey bearings of a hole are referenced. Values that are set take precedence over the collar.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AzimuthReference {
+    pub datum: Option<AzimuthDatum>,
+    pub declination: Option<f64>,
+}
+
+impl HoleRecord for Collar {
+    fn hole_id(&self) -> Option<&str> {
+        Some(&self.hole_id)
+    }
+}
+
+/// A downhole interval from a table such as lithology or alteration.
+/// `values` holds the remaining columns of the table by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interval {
+    pub hole_id: String,
+    pub from: f64,
+    pub to: f64,
+    #[serde(default)]
+    pub values: BTreeMap<String, String>,
+}
+
+impl HoleRecord for Interval {
+    fn hole_id(&self) -> Option<&str> {
+        Some(&self.hole_id)
+    }
+}
+
+/// A problem with how the tables of a `Project` refer to each other.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityIssue {
+    DuplicateCollar {
+        hole_id: String,
+    },
+    /// A table has rows for a hole that has no collar. `table` is the name of the table.
+    MissingCollar {
+        hole_id: String,
+        table: String,
+    },
+    /// A table has rows for a hole that has no survey, e.g. measurements that cannot be oriented
+    MissingSurvey {
+        hole_id: String,
+        table: String,
+    },
+    /// A row of a table is deeper than the end of hole depth of its collar
+    PastEndOfHole {
+        hole_id: String,
+        table: String,
+        depth: f64,
+        eoh: f64,
+    },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateCollar { hole_id } => write!(f, "{hole_id}: duplicate collar"),
+            Self::MissingCollar { hole_id, table } => {
+                write!(f, "{hole_id}: {table} without a collar")
+            }
+            Self::MissingSurvey { hole_id, table } => {
+                write!(f, "{hole_id}: {table} without a survey")
+            }
+            Self::PastEndOfHole {
+                hole_id,
+                table,
+                depth,
+                eoh,
+            } => write!(
+                f,
+                "{hole_id}: {table} at {depth} is past the end of hole at {eoh}"
+            ),
+        }
+    }
+}
+
+/// All the drill hole data of a project keyed by hole id.
+#[derive(Debug, Clone, Default)]
+pub struct Project {
+    pub collars: BTreeMap<String, Collar>,
+    pub surveys: BTreeMap<String, Vec<BHOrientation>>,
+    pub measurements: BTreeMap<String, Vec<RawMeasurement>>,
+    pub core_runs: BTreeMap<String, Vec<CoreRun>>,
+    /// Interval tables by table name, then hole id
+    pub interval_tables: BTreeMap<String, BTreeMap<String, Vec<Interval>>>,
+    /// Azimuth datums and declinations by hole id that take precedence over the collars
+    pub azimuth_references: BTreeMap<String, AzimuthReference>,
+    /// Azimuth datums by survey type that take precedence over the datum of the hole,
+    /// e.g. a gyro referred to true north in a hole with magnetic surveys
+    pub survey_datums: BTreeMap<String, AzimuthDatum>,
+    /// Used for the declination of holes with magnetic bearings that have no declination,
+    /// the bundled WMM by default
+    pub magnetic_model: Option<MagneticModel>,
+    /// The UTM zone of the collar coordinates, needed for grid bearings and grid north output
+    pub utm_zone: Option<UtmZone>,
+    /// The north the azimuths of oriented measurements refer to, see `Borehole::set_north`
+    pub output_north: AzimuthDatum,
+    /// The transform from a local mine grid to the UTM grid of the collars, needed for local azimuths
+    pub local_grid: Option<GridTransform>,
+    /// Survey types from the most to the least trusted, see `merge_surveys`
+    pub survey_priority: Vec<String>,
+    /// Smooth the merged survey of each hole before measurements are oriented with it, see `smooth_survey`
+    pub survey_smoothing: Option<SurveySmoothing>,
+    /// Hole ids that had more than one collar, only the first collar is kept
+    duplicate_collars: Vec<String>,
+}
+
+impl Project {
+    /// Create a project from flat tables.
+    /// Survey, measurement and run rows without a hole id are assigned to `default_hole_id`.
+    pub fn new(
+        collars: Vec<Collar>,
+        surveys: Vec<BHOrientation>,
+        measurements: Vec<RawMeasurement>,
+        core_runs: Vec<CoreRun>,
+        default_hole_id: &str,
+    ) -> Self {
+        let mut duplicate_collars = vec![];
+        let collars = group_by_hole(collars, default_hole_id)
+            .into_iter()
+            .map(|(hole_id, mut collars)| {
+                if collars.len() > 1 {
+                    duplicate_collars.push(hole_id.clone());
+                }
+                (hole_id, collars.swap_remove(0))
+            })
+            .collect();
+
+        Self {
+            collars,
+            surveys: group_by_hole(surveys, default_hole_id),
+            measurements: group_by_hole(measurements, default_hole_id),
+            core_runs: group_by_hole(core_runs, default_hole_id),
+            interval_tables: BTreeMap::new(),
+            azimuth_references: BTreeMap::new(),
+            survey_datums: BTreeMap::new(),
+            magnetic_model: Some(MagneticModel::wmm()),
+            utm_zone: None,
+            output_north: AzimuthDatum::True,
+            local_grid: None,
+            survey_priority: vec![],
+            survey_smoothing: None,
+            duplicate_collars,
+        }
+    }
+
+    pub fn add_interval_table(&mut self, name: &str, intervals: Vec<Interval>) {
+        self.interval_tables
+            .insert(name.to_string(), group_by_hole(intervals, ""));
+    }
+
+    /// Every hole id used by any of the tables
+    pub fn hole_ids(&self) -> BTreeSet<String> {
+        self.collars
+            .keys()
+            .chain(self.surveys.keys())
+            .chain(self.measurements.keys())
+            .chain(self.core_runs.keys())
+            .chain(self.interval_tables.values().flat_map(|table| table.keys()))
+            .cloned()
+            .collect()
+    }
+
+    /// Check that the tables refer to each other correctly.
+    /// Collar checks are skipped when the project has no collars.
+    pub fn validate(&self) -> Vec<IntegrityIssue> {
+        let mut issues = self
+            .duplicate_collars
+            .iter()
+            .map(|hole_id| IntegrityIssue::DuplicateCollar {
+                hole_id: hole_id.clone(),
+            })
+            .collect::<Vec<IntegrityIssue>>();
+
+        let mut tables: Vec<(&str, Vec<&String>)> = vec![
+            ("measurement", self.measurements.keys().collect()),
+            ("collar", self.collars.keys().collect()),
+            ("core run", self.core_runs.keys().collect()),
+        ];
+        for (name, table) in &self.interval_tables {
+            tables.push((name, table.keys().collect()));
+        }
+        for (table, hole_ids) in tables {
+            issues.extend(
+                hole_ids
+                    .into_iter()
+                    .filter(|hole_id| !self.surveys.contains_key(*hole_id))
+                    .map(|hole_id| IntegrityIssue::MissingSurvey {
+                        hole_id: hole_id.clone(),
+                        table: table.to_string(),
+                    }),
+            );
+        }
+
+        if self.collars.is_empty() {
+            return issues;
+        }
+
+        let mut tables: Vec<(String, BTreeMap<&String, Vec<f64>>)> = vec![
+            (
+                "survey".to_string(),
+                depths(&self.surveys, |station| station.depth),
+            ),
+            (
+                "measurement".to_string(),
+                depths(&self.measurements, |measurement| measurement.depth),
+            ),
+            (
+                "core run".to_string(),
+                depths(&self.core_runs, |run| run.to),
+            ),
+        ];
+        for (name, table) in &self.interval_tables {
+            tables.push((name.clone(), depths(table, |interval| interval.to)));
+        }
+
+        for (table, holes) in tables {
+            for (hole_id, depths) in holes {
+                match self.collars.get(hole_id) {
+                    None => issues.push(IntegrityIssue::MissingCollar {
+                        hole_id: hole_id.clone(),
+                        table: table.clone(),
+                    }),
+                    Some(collar) => {
+                        issues.extend(depths.into_iter().filter(|depth| *depth > collar.eoh).map(
+                            |depth| IntegrityIssue::PastEndOfHole {
+                                hole_id: hole_id.clone(),
+                                table: table.clone(),
+                                depth,
+                                eoh: collar.eoh,
+                            },
+                        ))
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// The angle in degrees added to the survey bearings of a hole to refer them to true north.
+    pub fn bearing_correction(&self, hole_id: &str) -> Result<f64, String> {
+        let datum = self
+            .azimuth_references
+            .get(hole_id)
+            .and_then(|reference| reference.datum)
+            .or(self.collars.get(hole_id).map(|collar| collar.azimuth_datum))
+            .unwrap_or_default();
+        self.north_rotation(hole_id, datum)
+    }
+
+    /// The angle in degrees added to the bearings of a survey of `survey_type` of a hole to refer them
+    /// to true north. The datum of the survey type in `survey_datums` takes precedence over the datum of
+    /// the hole, see `bearing_correction`.
+    pub fn survey_bearing_correction(
+        &self,
+        hole_id: &str,
+        survey_type: Option<&str>,
+    ) -> Result<f64, String> {
+        let datum = survey_type.and_then(|survey_type| {
+            self.survey_datums
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(survey_type))
+        });
+        match datum {
+            Some((_, datum)) => self.north_rotation(hole_id, *datum),
+            None => self.bearing_correction(hole_id),
+        }
+    }
+
+    /// The clockwise angle in degrees from true north to the north of `datum` at the collar of a hole.
+    /// This is the declination for magnetic north and the meridian convergence for grid north.
+    /// Local grid north is rotated from UTM grid north by the local grid transform.
+    pub fn north_rotation(&self, hole_id: &str, datum: AzimuthDatum) -> Result<f64, String> {
+        let collar = self.collars.get(hole_id);
+        match datum {
+            AzimuthDatum::True => Ok(0.0),
+            AzimuthDatum::Magnetic => {
+                if let Some(declination) = self
+                    .azimuth_references
+                    .get(hole_id)
+                    .and_then(|reference| reference.declination)
+                    .or(collar.and_then(|collar| collar.declination))
+                {
+                    return Ok(declination);
+                }
+                let missing = || {
+                    format!("{hole_id}: magnetic bearings need a declination, or a magnetic model and a collar with a date and a latitude and longitude or UTM zone")
+                };
+                let (model, collar) = self
+                    .magnetic_model
+                    .as_ref()
+                    .zip(collar)
+                    .ok_or_else(missing)?;
+                let ((latitude, longitude), date) = self
+                    .geodetic_position(collar)
+                    .zip(collar.date.as_ref())
+                    .ok_or_else(missing)?;
+                let year = decimal_year(date).map_err(|error| format!("{hole_id}: {error}"))?;
+                if !model.is_valid_at(year) {
+                    return Err(format!(
+                        "{hole_id}: {date} is outside of the {} magnetic model ({} to {}), give a declination or another model",
+                        model.name,
+                        model.epoch,
+                        model.epoch + 5.0
+                    ));
+                }
+                // Collar elevations are in metres, the model takes km
+                Ok(model.declination(latitude, longitude, collar.z / 1000.0, year))
+            }
+            AzimuthDatum::Grid => {
+                let zone = self.utm_zone.ok_or_else(|| {
+                    format!("{hole_id}: grid north needs the UTM zone of the collars")
+                })?;
+                let collar =
+                    collar.ok_or_else(|| format!("{hole_id}: grid north needs a collar"))?;
+                Ok(grid_convergence(collar.x, collar.y, zone))
+            }
+            AzimuthDatum::Local => {
+                let transform = self.local_grid.ok_or_else(|| {
+                    format!("{hole_id}: local grid north needs a local grid transform")
+                })?;
+                Ok(self.north_rotation(hole_id, AzimuthDatum::Grid)? + transform.rotation())
+            }
+        }
+    }
+
+    /// The latitude and longitude of a collar, projected from its coordinates when they are not recorded.
+    fn geodetic_position(&self, collar: &Collar) -> Option<(f64, f64)> {
+        match (collar.latitude, collar.longitude, self.utm_zone) {
+            (Some(latitude), Some(longitude), _) => Some((latitude, longitude)),
+            (_, _, Some(zone)) => Some(geodetic_from_utm(collar.x, collar.y, zone)),
+            _ => None,
+        }
+    }
+
+    /// Orient the measurements of one hole. The returned `Borehole` is a copy of the project data.
+    /// The bearings of each survey type are corrected to true north, see `survey_bearing_correction`,
+    /// before surveys of several types are merged, see `merge_surveys`.
+    /// The oriented measurements are referred to `output_north`.
+    pub fn borehole(
+        &self,
+        hole_id: &str,
+        orientation_line: BHOrientationLine,
+        inclination_convention: InclinationConvention,
+        beta_convention: BetaConvention,
+    ) -> Result<Borehole, String> {
+        let mut stations = self.surveys.get(hole_id).cloned().unwrap_or_default();
+        let mut corrections = BTreeMap::new();
+        for station in stations.iter_mut() {
+            let correction = match corrections.get(&station.survey_type) {
+                Some(&correction) => correction,
+                None => {
+                    let correction =
+                        self.survey_bearing_correction(hole_id, station.survey_type.as_deref())?;
+                    corrections.insert(station.survey_type.clone(), correction);
+                    correction
+                }
+            };
+            // Bearings that are out of range are left for `Borehole::try_new` to report
+            if correction != 0.0 && (0.0..=360.0).contains(&station.bearing) {
+                station.bearing = normalise_azimuth(station.bearing + correction);
+            }
+        }
+        let merged = merge_surveys(stations, &self.survey_priority, inclination_convention)?;
+        let mut surveys = merged.stations;
+        let mut smoothed_survey = vec![];
+        let mut unsmoothed_survey = vec![];
+        if let Some(smoothing) = &self.survey_smoothing {
+            unsmoothed_survey = validate_survey(surveys, inclination_convention)?;
+            let (smoothed, report) = smooth_survey(&unsmoothed_survey, smoothing);
+            surveys = smoothed
+                .into_iter()
+                .map(|station| BHOrientation {
+                    inclination: inclination_convention.from_negative_down(station.inclination),
+                    ..station
+                })
+                .collect();
+            smoothed_survey = report;
+        }
+        let mut borehole = Borehole::try_new(
+            orientation_line,
+            inclination_convention,
+            beta_convention,
+            self.measurements.get(hole_id).cloned().unwrap_or_default(),
+            surveys,
+            self.core_runs.get(hole_id).cloned().unwrap_or_default(),
+        )?;
+        borehole.survey_disagreements = merged.disagreements;
+        borehole.smoothed_survey = smoothed_survey;
+        borehole.unsmoothed_survey = unsmoothed_survey;
+        if let Some(collar) = self.collars.get(hole_id) {
+            if let Some(zone) = self.utm_zone {
+                borehole.set_grid_convergence(grid_convergence(collar.x, collar.y, zone));
+            }
+            borehole.set_collar(collar.clone());
+        }
+        if self.output_north != AzimuthDatum::True {
+            borehole.set_north(
+                self.output_north,
+                self.north_rotation(hole_id, self.output_north)?,
+            );
+        }
+        Ok(borehole)
+    }
+}
+
+fn depths<T>(
+    table: &BTreeMap<String, Vec<T>>,
+    depth: impl Fn(&T) -> f64,
+) -> BTreeMap<&String, Vec<f64>> {
+    table
+        .iter()
+        .map(|(hole_id, rows)| (hole_id, rows.iter().map(&depth).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{survey_qa::SurveyQaLimits, uncertainty::MeasurementErrors};
+
+    fn collar(hole_id: &str, eoh: f64) -> Collar {
+        Collar {
+            hole_id: hole_id.to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            eoh,
+            date: None,
+            azimuth_datum: AzimuthDatum::True,
+            declination: None,
+            latitude: None,
+            longitude: None,
+        }
+    }
+
+    fn station(hole_id: &str, depth: f64) -> BHOrientation {
+        BHOrientation {
+            hole_id: Some(hole_id.to_string()),
+            survey_type: None,
+            depth,
+            bearing: 262.7,
+            inclination: -55.3,
+        }
+    }
+
+    fn measurement(hole_id: &str, depth: f64) -> RawMeasurement {
+        RawMeasurement {
+            hole_id: Some(hole_id.to_string()),
+            depth,
+            alpha: 65.0,
+            beta: Some(230.0),
+            orientation_line: None,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn project_validate() {
+        let mut project = Project::new(
+            vec![
+                collar("DH1", 100.0),
+                collar("DH1", 120.0),
+                collar("DH2", 50.0),
+            ],
+            vec![
+                station("DH1", 0.0),
+                station("DH1", 90.0),
+                station("DH3", 0.0),
+            ],
+            vec![measurement("DH1", 110.0), measurement("DH2", 20.0)],
+            vec![],
+            "",
+        );
+        project.add_interval_table(
+            "lithology",
+            vec![Interval {
+                hole_id: "DH2".to_string(),
+                from: 0.0,
+                to: 60.0,
+                values: BTreeMap::new(),
+            }],
+        );
+        let issues = project.validate();
+
+        assert!(issues.contains(&IntegrityIssue::DuplicateCollar {
+            hole_id: "DH1".to_string()
+        }));
+        // DH2 has a collar, measurements and intervals but no survey
+        for table in ["measurement", "collar", "lithology"] {
+            assert!(issues.contains(&IntegrityIssue::MissingSurvey {
+                hole_id: "DH2".to_string(),
+                table: table.to_string()
+            }));
+        }
+        assert!(issues.contains(&IntegrityIssue::MissingCollar {
+            hole_id: "DH3".to_string(),
+            table: "survey".to_string()
+        }));
+        assert!(issues.contains(&IntegrityIssue::PastEndOfHole {
+            hole_id: "DH1".to_string(),
+            table: "measurement".to_string(),
+            depth: 110.0,
+            eoh: 100.0
+        }));
+        assert!(issues.contains(&IntegrityIssue::PastEndOfHole {
+            hole_id: "DH2".to_string(),
+            table: "lithology".to_string(),
+            depth: 60.0,
+            eoh: 50.0
+        }));
+        assert_eq!(issues.len(), 7);
+        assert_eq!(project.hole_ids().len(), 3);
+    }
+
+    #[test]
+    fn project_borehole() {
+        let project = Project::new(
+            vec![collar("DH1", 100.0)],
+            vec![station("DH1", 0.0), station("DH1", 90.0)],
+            vec![measurement("DH1", 50.0)],
+            vec![],
+            "",
+        );
+        let borehole = project
+            .borehole(
+                "DH1",
+                BHOrientationLine::Top,
+                InclinationConvention::NegativeDown,
+                BetaConvention::default(),
+            )
+            .unwrap();
+
+        assert_eq!(borehole.collar.as_ref().unwrap().eoh, 100.0);
+        let measurement = &borehole.oriented_measurements[0];
+        let location = measurement.location.unwrap();
+        assert!((location.z + 50.0 * 55.3f64.to_radians().sin()).abs() < 1e-9);
+        assert_eq!(measurement.raw.hole_id.as_deref(), Some("DH1"));
+        assert_eq!(measurement.bearing, 262.7);
+        assert_eq!(
+            borehole.oriented_measurements[0]
+                .plane
+                .unwrap()
+                .pole
+                .trend
+                .round(),
+            286.0
+        );
+        assert!(project
+            .borehole(
+                "DH2",
+                BHOrientationLine::Top,
+                InclinationConvention::NegativeDown,
+                BetaConvention::default(),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn project_merged_surveys() {
+        let typed = |survey_type: &str, depth: f64, bearing: f64| BHOrientation {
+            survey_type: Some(survey_type.to_string()),
+            bearing,
+            ..station("DH1", depth)
+        };
+        let mut project = Project::new(
+            vec![],
+            vec![
+                typed("magnetic", 0.0, 250.0),
+                typed("magnetic", 60.0, 250.0),
+                typed("magnetic", 120.0, 250.0),
+                typed("gyro", 0.0, 262.7),
+                typed("gyro", 90.0, 262.7),
+            ],
+            vec![measurement("DH1", 50.0), measurement("DH1", 110.0)],
+            vec![],
+            "",
+        );
+        let borehole = |project: &Project| {
+            project
+                .borehole(
+                    "DH1",
+                    BHOrientationLine::Top,
+                    InclinationConvention::NegativeDown,
+                    BetaConvention::default(),
+                )
+                .unwrap()
+        };
+        let merged = borehole(&project);
+        let bearings = merged
+            .hole_orientation
+            .iter()
+            .map(|station| (station.depth, station.bearing))
+            .collect::<Vec<(f64, f64)>>();
+        assert_eq!(bearings, vec![(0.0, 262.7), (90.0, 262.7), (120.0, 250.0)]);
+        assert_eq!(merged.oriented_measurements[0].bearing, 262.7);
+        assert_eq!(merged.oriented_measurements[1].bearing, 250.0);
+        assert_eq!(merged.survey_disagreements.len(), 2);
+        assert!(merged
+            .survey_disagreements
+            .iter()
+            .all(|disagreement| (disagreement.bearing_difference + 12.7).abs() < 1e-9));
+
+        project.survey_priority = vec!["magnetic".to_string()];
+        let merged = borehole(&project);
+        assert_eq!(merged.hole_orientation.len(), 3);
+        assert_eq!(merged.oriented_measurements[0].bearing, 250.0);
+    }
+
+    #[test]
+    fn project_survey_datums() {
+        let typed = |survey_type: &str, depth: f64, bearing: f64| BHOrientation {
+            survey_type: Some(survey_type.to_string()),
+            bearing,
+            ..station("DH1", depth)
+        };
+        let mut magnetic = collar("DH1", 150.0);
+        magnetic.azimuth_datum = AzimuthDatum::Magnetic;
+        magnetic.declination = Some(12.7);
+        let mut project = Project::new(
+            vec![magnetic],
+            vec![
+                typed("magnetic", 0.0, 250.0),
+                typed("magnetic", 60.0, 250.0),
+                typed("magnetic", 120.0, 250.0),
+                typed("gyro", 0.0, 262.7),
+                typed("gyro", 90.0, 262.7),
+            ],
+            vec![measurement("DH1", 50.0), measurement("DH1", 110.0)],
+            vec![],
+            "",
+        );
+        project
+            .survey_datums
+            .insert("Gyro".to_string(), AzimuthDatum::True);
+        assert_eq!(
+            project
+                .survey_bearing_correction("DH1", Some("gyro"))
+                .unwrap(),
+            0.0
+        );
+        assert_eq!(
+            project
+                .survey_bearing_correction("DH1", Some("magnetic"))
+                .unwrap(),
+            12.7
+        );
+
+        // The magnetic survey is corrected by the declination before it is merged with the gyro
+        let borehole = project
+            .borehole(
+                "DH1",
+                BHOrientationLine::Top,
+                InclinationConvention::NegativeDown,
+                BetaConvention::default(),
+            )
+            .unwrap();
+        assert!(borehole
+            .hole_orientation
+            .iter()
+            .all(|station| (station.bearing - 262.7).abs() < 1e-9));
+        assert!(borehole
+            .oriented_measurements
+            .iter()
+            .all(|measurement| (measurement.bearing - 262.7).abs() < 1e-9));
+        assert_eq!(borehole.survey_disagreements.len(), 2);
+        assert!(borehole
+            .survey_disagreements
+            .iter()
+            .all(|disagreement| disagreement.bearing_difference.abs() < 1e-9));
+    }
+
+    #[test]
+    fn project_smoothed_survey() {
+        let mut stations = (0..7)
+            .map(|i| BHOrientation {
+                inclination: 55.3,
+                ..station("DH1", i as f64 * 30.0)
+            })
+            .collect::<Vec<BHOrientation>>();
+        stations[3].bearing = 280.0;
+        let mut project =
+            Project::new(vec![], stations, vec![measurement("DH1", 90.0)], vec![], "");
+        project.survey_smoothing = Some(SurveySmoothing::default());
+        let mut borehole = project
+            .borehole(
+                "DH1",
+                BHOrientationLine::Top,
+                InclinationConvention::PositiveDown,
+                BetaConvention::default(),
+            )
+            .unwrap();
+        assert_eq!(borehole.hole_orientation.len(), 6);
+        assert!(borehole.hole_orientation.iter().all(|station| {
+            (station.bearing - 262.7).abs() < 1e-9 && (station.inclination + 55.3).abs() < 1e-9
+        }));
+        assert_eq!(borehole.smoothed_survey.len(), 7);
+        assert!(borehole.smoothed_survey[3].rejected);
+        assert_eq!(borehole.smoothed_survey[3].inclination, -55.3);
+        // The measurement is oriented with the stations either side of the rejected one
+        let expected = Borehole::new(
+            BHOrientationLine::Top,
+            InclinationConvention::PositiveDown,
+            BetaConvention::default(),
+            vec![measurement("DH1", 90.0)],
+            vec![BHOrientation {
+                inclination: 55.3,
+                ..station("DH1", 0.0)
+            }],
+            vec![],
+        );
+        assert!(
+            (borehole.oriented_measurements[0].bearing - expected.oriented_measurements[0].bearing)
+                .abs()
+                < 1e-9
+        );
+
+        // The checks see the survey before the rejected station was removed
+        borehole.check_survey(&SurveyQaLimits::default());
+        assert_eq!(borehole.survey_qa.len(), 7);
+        assert!(borehole.survey_qa[3].is_flagged());
+        assert!(!borehole.oriented_measurements[0].survey_flags.is_empty());
+    }
+
+    #[test]
+    fn project_magnetic_bearings() {
+        let mut magnetic = collar("DH1", 100.0);
+        magnetic.azimuth_datum = AzimuthDatum::Magnetic;
+        let mut project = Project::new(
+            vec![magnetic.clone()],
+            vec![station("DH1", 0.0)],
+            vec![],
+            vec![],
+            "",
+        );
+        assert!(project.bearing_correction("DH1").is_err());
+        assert_eq!(project.bearing_correction("DH2").unwrap(), 0.0);
+
+        project.azimuth_references.insert(
+            "DH1".to_string(),
+            AzimuthReference {
+                datum: None,
+                declination: Some(100.0),
+            },
+        );
+        let borehole = project
+            .borehole(
+                "DH1",
+                BHOrientationLine::Top,
+                InclinationConvention::NegativeDown,
+                BetaConvention::default(),
+            )
+            .unwrap();
+        assert!((borehole.hole_orientation[0].bearing - 2.7).abs() < 1e-9);
+
+        // The declination is computed from the model at the collar
+        project.azimuth_references.clear();
+        project.magnetic_model = Some(
+            MagneticModel::from_cof(
+                "2020.0 TEST\n1 0 -30000.0 0.0 0.0 0.0\n1 1 0.0 5000.0 0.0 0.0\n999999\n",
+            )
+            .unwrap(),
+        );
+        magnetic.latitude = Some(0.0);
+        magnetic.longitude = Some(0.0);
+        magnetic.date = Some("2021-06-01".to_string());
+        project.collars.insert("DH1".to_string(), magnetic.clone());
+        let expected = (-5000f64).atan2(30000.0).to_degrees();
+        assert!((project.bearing_correction("DH1").unwrap() - expected).abs() < 1e-9);
+
+        magnetic.date = Some("2030-06-01".to_string());
+        project.collars.insert("DH1".to_string(), magnetic);
+        assert!(project.bearing_correction("DH1").is_err());
+    }
+
+    #[test]
+    fn project_grid_north() {
+        let zone = UtmZone::new(55, false).unwrap();
+        let mut grid = collar("DH1", 100.0);
+        grid.azimuth_datum = AzimuthDatum::Grid;
+        (grid.x, grid.y) = (650_000.0, 5_800_000.0);
+        let mut project = Project::new(
+            vec![grid],
+            vec![station("DH1", 0.0)],
+            vec![measurement("DH1", 10.0)],
+            vec![],
+            "",
+        );
+        assert!(project.bearing_correction("DH1").is_err());
+
+        project.utm_zone = Some(zone);
+        let convergence = grid_convergence(650_000.0, 5_800_000.0, zone);
+        // East of the central meridian in the south grid north is west of true north
+        assert!(convergence < -1.0);
+        assert_eq!(project.bearing_correction("DH1").unwrap(), convergence);
+
+        let errors = MeasurementErrors {
+            bearing: 1.0,
+            inclination: 1.0,
+            alpha: 1.0,
+            beta: 1.0,
+        };
+        let orient = |project: &Project| {
+            let mut borehole = project
+                .borehole(
+                    "DH1",
+                    BHOrientationLine::Top,
+                    InclinationConvention::NegativeDown,
+                    BetaConvention::default(),
+                )
+                .unwrap();
+            borehole.propagate_errors(&errors);
+            borehole.oriented_measurements.remove(0)
+        };
+        let true_north = orient(&project);
+        assert_eq!(true_north.north, AzimuthDatum::True);
+        assert!((true_north.bearing - normalise_azimuth(262.7 + convergence)).abs() < 1e-9);
+        // The measurements are located along the survey rotated to grid north
+        let location = true_north.location.unwrap();
+        let (east, north) = (location.x - 650_000.0, location.y - 5_800_000.0);
+        assert!((normalise_azimuth(east.atan2(north).to_degrees()) - 262.7).abs() < 1e-9);
+
+        project.output_north = AzimuthDatum::Grid;
+        let grid_north = orient(&project);
+        assert_eq!(grid_north.north, AzimuthDatum::Grid);
+        assert!((grid_north.bearing - 262.7).abs() < 1e-9);
+        let rotated = |true_azimuth: f64, grid_azimuth: f64| {
+            (normalise_azimuth(true_azimuth - convergence) - grid_azimuth).abs() < 1e-9
+        };
+        let (true_plane, grid_plane) = (true_north.plane.unwrap(), grid_north.plane.unwrap());
+        assert!(rotated(true_plane.dip_direction, grid_plane.dip_direction));
+        assert!(rotated(true_plane.pole.trend, grid_plane.pole.trend));
+        assert!((true_plane.dip - grid_plane.dip).abs() < 1e-9);
+        let (true_ellipse, grid_ellipse) = (
+            true_north.uncertainty.unwrap().ellipse,
+            grid_north.uncertainty.unwrap().ellipse,
+        );
+        assert!(rotated(
+            true_ellipse.major_axis.trend,
+            grid_ellipse.major_axis.trend
+        ));
+    }
+
+    #[test]
+    fn project_local_grid_north() {
+        let zone = UtmZone::new(55, false).unwrap();
+        let mut local = collar("DH1", 100.0);
+        local.azimuth_datum = AzimuthDatum::Local;
+        (local.x, local.y) = (650_000.0, 5_800_000.0);
+        let mut project = Project::new(
+            vec![local],
+            vec![station("DH1", 0.0)],
+            vec![measurement("DH1", 10.0)],
+            vec![],
+            "",
+        );
+        project.utm_zone = Some(zone);
+        assert!(project.bearing_correction("DH1").is_err());
+
+        // Local grid north is 10° east of UTM grid north
+        project.local_grid = Some(
+            GridTransform::similarity(10.0, 1.0, (0.0, 0.0), (650_000.0, 5_800_000.0)).unwrap(),
+        );
+        let convergence = grid_convergence(650_000.0, 5_800_000.0, zone);
+        let rotation = project.north_rotation("DH1", AzimuthDatum::Local).unwrap();
+        assert!((rotation - (convergence + 10.0)).abs() < 1e-9);
+        assert!((project.bearing_correction("DH1").unwrap() - rotation).abs() < 1e-9);
+
+        let orient = |project: &Project| {
+            project
+                .borehole(
+                    "DH1",
+                    BHOrientationLine::Top,
+                    InclinationConvention::NegativeDown,
+                    BetaConvention::default(),
+                )
+                .unwrap()
+                .oriented_measurements
+                .remove(0)
+        };
+        let true_north = orient(&project);
+        assert!((true_north.bearing - normalise_azimuth(262.7 + rotation)).abs() < 1e-9);
+        // Located along the local bearing referred to UTM grid north
+        let location = true_north.location.unwrap();
+        let (east, north) = (location.x - 650_000.0, location.y - 5_800_000.0);
+        assert!((normalise_azimuth(east.atan2(north).to_degrees()) - 272.7).abs() < 1e-6);
+
+        project.output_north = AzimuthDatum::Local;
+        let local_north = orient(&project);
+        assert_eq!(local_north.north, AzimuthDatum::Local);
+        assert!((local_north.bearing - 262.7).abs() < 1e-9);
+        let (true_plane, local_plane) = (true_north.plane.unwrap(), local_north.plane.unwrap());
+        assert!(
+            (normalise_azimuth(true_plane.dip_direction - rotation) - local_plane.dip_direction)
+                .abs()
+                < 1e-9
+        );
+    }
+}
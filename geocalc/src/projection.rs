@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::validation::error_if_out_of_range;
+
+/// WGS84 semi-major axis in metres
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// The scale factor on the central meridian of a UTM zone
+const UTM_K0: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// A Universal Transverse Mercator zone, parses from e.g. `55S` or `33N`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct UtmZone {
+    /// The zone number from 1 to 60
+    pub number: u8,
+    /// True for the northern hemisphere
+    pub north: bool,
+}
+
+impl UtmZone {
+    pub fn new(number: u8, north: bool) -> Result<Self, String> {
+        match number {
+            1..=60 => Ok(Self { number, north }),
+            _ => Err(format!("UTM zone {number} is not between 1 and 60")),
+        }
+    }
+
+    /// The zone a point falls in, including the exceptions for southwest Norway and Svalbard.
+    /// UTM is only defined from 80°S to 84°N.
+    pub fn containing(latitude: f64, longitude: f64) -> Result<Self, String> {
+        error_if_out_of_range(&latitude, -80.0, 84.0)
+            .map_err(|error| format!("Latitude {error}, UTM is only defined from 80°S to 84°N"))?;
+        error_if_out_of_range(&longitude, -180.0, 180.0)
+            .map_err(|error| format!("Longitude {error}"))?;
+        let number = match (latitude, longitude) {
+            (56.0..=64.0, 3.0..=12.0) => 32,
+            (72.0..=84.0, 0.0..=42.0) => match longitude {
+                lon if lon < 9.0 => 31,
+                lon if lon < 21.0 => 33,
+                lon if lon < 33.0 => 35,
+                _ => 37,
+            },
+            // 180° belongs to zone 60 rather than a zone 61
+            _ => (((longitude + 180.0) / 6.0).floor() as u8 + 1).min(60),
+        };
+        Self::new(number, latitude >= 0.0)
+    }
+
+    /// The longitude of the central meridian in degrees
+    pub fn central_meridian(&self) -> f64 {
+        self.number as f64 * 6.0 - 183.0
+    }
+}
+
+impl FromStr for UtmZone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid =
+            || format!("Unknown UTM zone {s}, expected a zone number and N or S, e.g. 55S");
+        let hemisphere = s.chars().last().ok_or_else(invalid)?;
+        let north = match hemisphere.to_ascii_uppercase() {
+            'N' => true,
+            'S' => false,
+            _ => return Err(invalid()),
+        };
+        let number = s[..s.len() - 1]
+            .trim()
+            .parse::<u8>()
+            .map_err(|_| invalid())?;
+        Self::new(number, north)
+    }
+}
+
+impl fmt::Display for UtmZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.number, if self.north { "N" } else { "S" })
+    }
+}
+
+impl TryFrom<String> for UtmZone {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<UtmZone> for String {
+    fn from(value: UtmZone) -> Self {
+        value.to_string()
+    }
+}
+
+/// A point projected to a UTM zone
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct UtmPoint {
+    pub easting: f64,
+    pub northing: f64,
+    /// The meridian convergence in degrees, the angle from true north to grid north measured clockwise
+    pub convergence: f64,
+}
+
+/// The coefficients of the Krüger series on the WGS84 ellipsoid to sixth order in the third flattening,
+/// see Karney (2011) Transverse Mercator with an accuracy of a few nanometers.
+struct Kruger {
+    /// The radius of the rectifying sphere
+    a: f64,
+    /// First eccentricity
+    e: f64,
+    alpha: [f64; 6],
+    beta: [f64; 6],
+}
+
+impl Kruger {
+    fn wgs84() -> Self {
+        let n = WGS84_F / (2.0 - WGS84_F);
+        let (n2, n3, n4, n5, n6) = (n.powi(2), n.powi(3), n.powi(4), n.powi(5), n.powi(6));
+        Self {
+            a: WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0 + n6 / 256.0),
+            e: (WGS84_F * (2.0 - WGS84_F)).sqrt(),
+            alpha: [
+                n / 2.0 - 2.0 / 3.0 * n2 + 5.0 / 16.0 * n3 + 41.0 / 180.0 * n4 - 127.0 / 288.0 * n5
+                    + 7891.0 / 37800.0 * n6,
+                13.0 / 48.0 * n2 - 3.0 / 5.0 * n3 + 557.0 / 1440.0 * n4 + 281.0 / 630.0 * n5
+                    - 1983433.0 / 1935360.0 * n6,
+                61.0 / 240.0 * n3 - 103.0 / 140.0 * n4
+                    + 15061.0 / 26880.0 * n5
+                    + 167603.0 / 181440.0 * n6,
+                49561.0 / 161280.0 * n4 - 179.0 / 168.0 * n5 + 6601661.0 / 7257600.0 * n6,
+                34729.0 / 80640.0 * n5 - 3418889.0 / 1995840.0 * n6,
+                212378941.0 / 319334400.0 * n6,
+            ],
+            beta: [
+                n / 2.0 - 2.0 / 3.0 * n2 + 37.0 / 96.0 * n3 - 1.0 / 360.0 * n4 - 81.0 / 512.0 * n5
+                    + 96199.0 / 604800.0 * n6,
+                n2 / 48.0 + n3 / 15.0 - 437.0 / 1440.0 * n4 + 46.0 / 105.0 * n5
+                    - 1118711.0 / 3870720.0 * n6,
+                17.0 / 480.0 * n3 - 37.0 / 840.0 * n4 - 209.0 / 4480.0 * n5 + 5569.0 / 90720.0 * n6,
+                4397.0 / 161280.0 * n4 - 11.0 / 504.0 * n5 - 830251.0 / 7257600.0 * n6,
+                4583.0 / 161280.0 * n5 - 108847.0 / 3991680.0 * n6,
+                20648693.0 / 638668800.0 * n6,
+            ],
+        }
+    }
+
+    /// The tangent of the conformal latitude from the tangent of the geodetic latitude
+    fn conformal(&self, tau: f64) -> f64 {
+        let sigma = (self.e * (self.e * tau / (1.0 + tau * tau).sqrt()).atanh()).sinh();
+        tau * (1.0 + sigma * sigma).sqrt() - sigma * (1.0 + tau * tau).sqrt()
+    }
+
+    /// Returns (x, y, convergence) on the unscaled projection, angles in radians
+    fn forward(&self, latitude: f64, longitude: f64) -> (f64, f64, f64) {
+        let tau_prime = self.conformal(latitude.tan());
+        let xi_prime = tau_prime.atan2(longitude.cos());
+        let eta_prime =
+            (longitude.sin() / (tau_prime.powi(2) + longitude.cos().powi(2)).sqrt()).asinh();
+
+        let (mut xi, mut eta) = (xi_prime, eta_prime);
+        let (mut p, mut q) = (1.0, 0.0);
+        for (j, alpha) in self.alpha.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            xi += alpha * (k * xi_prime).sin() * (k * eta_prime).cosh();
+            eta += alpha * (k * xi_prime).cos() * (k * eta_prime).sinh();
+            p += k * alpha * (k * xi_prime).cos() * (k * eta_prime).cosh();
+            q += k * alpha * (k * xi_prime).sin() * (k * eta_prime).sinh();
+        }
+        let convergence =
+            (tau_prime / (1.0 + tau_prime.powi(2)).sqrt() * longitude.tan()).atan() + q.atan2(p);
+        (self.a * eta, self.a * xi, convergence)
+    }
+
+    /// Returns (latitude, longitude) in radians from coordinates on the unscaled projection
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let (xi, eta) = (y / self.a, x / self.a);
+        let (mut xi_prime, mut eta_prime) = (xi, eta);
+        for (j, beta) in self.beta.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            xi_prime -= beta * (k * xi).sin() * (k * eta).cosh();
+            eta_prime -= beta * (k * xi).cos() * (k * eta).sinh();
+        }
+        let tau_prime = xi_prime.sin() / (eta_prime.sinh().powi(2) + xi_prime.cos().powi(2)).sqrt();
+        let longitude = eta_prime.sinh().atan2(xi_prime.cos());
+
+        // Newton's method for the geodetic latitude
+        let e2 = self.e * self.e;
+        let mut tau = tau_prime;
+        for _ in 0..10 {
+            let error = tau_prime - self.conformal(tau);
+            let step = error / (1.0 + self.conformal(tau).powi(2)).sqrt()
+                * (1.0 + (1.0 - e2) * tau * tau)
+                / ((1.0 - e2) * (1.0 + tau * tau).sqrt());
+            tau += step;
+            if step.abs() < 1e-14 {
+                break;
+            }
+        }
+        (tau.atan(), longitude)
+    }
+}
+
+/// Project a geodetic `latitude` and `longitude` (degrees, WGS84) to a UTM zone.
+pub fn utm_from_geodetic(latitude: f64, longitude: f64, zone: UtmZone) -> UtmPoint {
+    let longitude = (longitude - zone.central_meridian() + 180.0).rem_euclid(360.0) - 180.0;
+    let (x, y, convergence) =
+        Kruger::wgs84().forward(latitude.to_radians(), longitude.to_radians());
+    UtmPoint {
+        easting: UTM_FALSE_EASTING + UTM_K0 * x,
+        northing: UTM_K0 * y
+            + if zone.north {
+                0.0
+            } else {
+                UTM_FALSE_NORTHING_SOUTH
+            },
+        convergence: convergence.to_degrees(),
+    }
+}
+
+/// Returns the geodetic (latitude, longitude) in degrees (WGS84) of a point in a UTM zone.
+pub fn geodetic_from_utm(easting: f64, northing: f64, zone: UtmZone) -> (f64, f64) {
+    let x = (easting - UTM_FALSE_EASTING) / UTM_K0;
+    let y = (northing
+        - if zone.north {
+            0.0
+        } else {
+            UTM_FALSE_NORTHING_SOUTH
+        })
+        / UTM_K0;
+    let (latitude, longitude) = Kruger::wgs84().inverse(x, y);
+    let longitude =
+        (longitude.to_degrees() + zone.central_meridian() + 180.0).rem_euclid(360.0) - 180.0;
+    (latitude.to_degrees(), longitude)
+}
+
+/// The meridian convergence in degrees at a point in a UTM zone, the angle from true north to grid north
+/// measured clockwise. A true bearing is converted to a grid bearing by subtracting it.
+pub fn grid_convergence(easting: f64, northing: f64, zone: UtmZone) -> f64 {
+    let (latitude, longitude) = geodetic_from_utm(easting, northing, zone);
+    utm_from_geodetic(latitude, longitude, zone).convergence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utm_zone_from_str() {
+        assert_eq!(
+            "55S".parse::<UtmZone>().unwrap(),
+            UtmZone::new(55, false).unwrap()
+        );
+        assert_eq!("33n".parse::<UtmZone>().unwrap().to_string(), "33N");
+        assert_eq!(UtmZone::new(31, true).unwrap().central_meridian(), 3.0);
+        assert!("61N".parse::<UtmZone>().is_err());
+        assert!("55".parse::<UtmZone>().is_err());
+    }
+
+    #[test]
+    fn utm_zone_containing() {
+        let zone = |latitude, longitude| {
+            UtmZone::containing(latitude, longitude)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(zone(-37.8, 144.9), "55S");
+        assert_eq!(zone(51.5, -0.1), "30N");
+        assert_eq!(zone(0.0, 0.0), "31N");
+        assert_eq!(zone(-0.1, -180.0), "1S");
+        assert_eq!(zone(10.0, 180.0), "60N");
+        // Southwest Norway and Svalbard
+        assert_eq!(zone(60.4, 5.3), "32N");
+        assert_eq!(zone(78.2, 15.6), "33N");
+        assert_eq!(zone(78.0, 34.0), "37N");
+        assert!(UtmZone::containing(84.5, 10.0).is_err());
+        assert!(UtmZone::containing(-10.0, 190.0).is_err());
+    }
+
+    #[test]
+    fn utm_central_meridian() {
+        let zone = UtmZone::new(31, true).unwrap();
+        let point = utm_from_geodetic(45.0, 3.0, zone);
+        // The WGS84 meridian arc from the equator to 45° is 4984944.378 m
+        assert!((point.easting - 500_000.0).abs() < 1e-6);
+        assert!((point.northing - 4_984_944.378 * UTM_K0).abs() < 1e-2);
+        assert!(point.convergence.abs() < 1e-12);
+
+        let south = utm_from_geodetic(-45.0, 3.0, UtmZone::new(31, false).unwrap());
+        assert!((south.northing - (10_000_000.0 - 4_984_944.378 * UTM_K0)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn utm_reference_points() {
+        // 33.3N 44.4E is the example of the GeographicLib GeoConvert documentation, 38n 444140.54 3684706.36.
+        // The others are exact transverse Mercator values (Lee 1976) evaluated to 30 digits
+        // as the meridian arc at the complex latitude, independently of the Krüger series.
+        for (latitude, longitude, zone, easting, northing) in [
+            (33.3, 44.4, "38N", 444140.5449, 3684706.3555),
+            (60.0, 9.0, "31N", 834359.6679, 6666593.5721),
+            (64.1, -21.9, "27N", 456137.5541, 7108467.4170),
+            (-3.0, 38.0, "37S", 388870.8676, 9668356.0619),
+            (-23.5, -46.6, "23S", 336625.1319, 7400218.8602),
+            (-33.9, 151.2, "56S", 333568.9410, 6247473.3368),
+            (-45.0, 150.0, "55S", 736446.0261, 5012670.4953),
+        ] {
+            let zone = zone.parse::<UtmZone>().unwrap();
+            let point = utm_from_geodetic(latitude, longitude, zone);
+            assert!(
+                (point.easting - easting).abs() < 1e-3,
+                "{latitude} {longitude}"
+            );
+            assert!(
+                (point.northing - northing).abs() < 1e-3,
+                "{latitude} {longitude}"
+            );
+            let (back_latitude, back_longitude) = geodetic_from_utm(easting, northing, zone);
+            assert!((back_latitude - latitude).abs() < 1e-8);
+            assert!((back_longitude - longitude).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn utm_round_trip() {
+        let zone = UtmZone::new(55, false).unwrap();
+        for (latitude, longitude) in [
+            (-37.8, 144.9),
+            (-20.0, 150.0),
+            (-0.5, 143.2),
+            (-60.0, 146.9),
+        ] {
+            let point = utm_from_geodetic(latitude, longitude, zone);
+            let (back_latitude, back_longitude) =
+                geodetic_from_utm(point.easting, point.northing, zone);
+            assert!((back_latitude - latitude).abs() < 1e-10);
+            assert!((back_longitude - longitude).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn utm_convergence() {
+        // Close to the spherical approximation, positive east of the central meridian in the north
+        let zone = UtmZone::new(31, true).unwrap();
+        let point = utm_from_geodetic(45.0, 6.0, zone);
+        let spherical = (3f64.to_radians().tan() * 45f64.to_radians().sin())
+            .atan()
+            .to_degrees();
+        assert!((point.convergence - spherical).abs() < 0.01);
+        assert!(
+            (grid_convergence(point.easting, point.northing, zone) - point.convergence).abs()
+                < 1e-9
+        );
+
+        let south = utm_from_geodetic(-45.0, 6.0, UtmZone::new(31, false).unwrap());
+        assert!((south.convergence + point.convergence).abs() < 1e-9);
+    }
+}
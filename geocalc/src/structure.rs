@@ -1,6 +1,9 @@
 use crate::{
     borehole::{BHOrientationLine, Orient},
-    utils::{dip_direction_from_strike, plunge_from_dip, trend_from_strike},
+    utils::{
+        dip_direction_from_strike, dip_from_plunge, normalise_azimuth, plunge_from_dip,
+        strike_from_trend, trend_from_strike,
+    },
     validation::error_if_out_of_range,
 };
 use serde::Serialize;
@@ -22,6 +25,14 @@ impl Lineation {
         error_if_out_of_range(&plunge, 0.0, 90.0).unwrap();
         Self { trend, plunge }
     }
+
+    /// Rotate the trend clockwise about the vertical by `angle` degrees, e.g. to refer it to another north.
+    pub fn rotate_azimuth(&self, angle: f64) -> Self {
+        Self {
+            trend: normalise_azimuth(self.trend + angle),
+            plunge: self.plunge,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize)]
@@ -70,6 +81,24 @@ impl Plane {
         }
     }
 
+    /// Create a new `Plane` from its pole (the downward pointing normal).
+    pub fn from_pole(pole: Lineation) -> Self {
+        let strike = strike_from_trend(&pole.trend);
+
+        Self::new(
+            strike,
+            dip_from_plunge(&pole.plunge),
+            Some(dip_direction_from_strike(&strike)),
+            Some(pole.trend),
+            Some(pole.plunge),
+        )
+    }
+
+    /// Rotate the plane clockwise about the vertical by `angle` degrees, e.g. to refer it to another north.
+    pub fn rotate_azimuth(&self, angle: f64) -> Self {
+        Self::from_pole(self.pole.rotate_azimuth(angle))
+    }
+
     /// Create a new `Plane` from oriented borehole measurements.
     pub fn alpha_beta(
         bearing: f64,
@@ -81,4 +110,19 @@ impl Plane {
         let orient = Orient::new(bearing, inclination, alpha, beta, orientation_line);
         orient.into_plane()
     }
+
+    /// Both possible planes for a measurement where it is unclear whether the orientation mark is on the top
+    /// or the bottom of the core. This is common in up-holes where the mark was transferred by hand.
+    /// Returns `[top, bottom]`.
+    pub fn alpha_beta_candidates(
+        bearing: f64,
+        inclination: f64,
+        alpha: f64,
+        beta: f64,
+    ) -> [Self; 2] {
+        [
+            Self::alpha_beta(bearing, inclination, alpha, beta, BHOrientationLine::Top),
+            Self::alpha_beta(bearing, inclination, alpha, beta, BHOrientationLine::Bottom),
+        ]
+    }
 }
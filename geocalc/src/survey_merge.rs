@@ -0,0 +1,255 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    borehole::{BHOrientation, InclinationConvention},
+    desurvey::{direction, slerp},
+    utils::normalise_azimuth,
+};
+
+/// Survey types from the most to the least trusted, used when no priority is given
+pub const DEFAULT_SURVEY_PRIORITY: [&str; 4] = ["gyro", "multishot", "magnetic", "planned"];
+
+/// Where a station of a lower priority survey disagrees with the survey chosen at its depth
+#[derive(Debug, Clone, Serialize)]
+pub struct SurveyDisagreement {
+    pub depth: f64,
+    /// The type of the survey that was overridden
+    pub survey_type: Option<String>,
+    /// The type of the survey used at this depth
+    pub chosen_type: Option<String>,
+    /// The overridden bearing minus the chosen bearing, between -180° and 180°
+    pub bearing_difference: f64,
+    /// The overridden inclination minus the chosen inclination
+    pub inclination_difference: f64,
+    /// The angle in degrees between the two hole directions
+    pub angle: f64,
+}
+
+/// A survey merged from several survey types
+#[derive(Debug, Clone)]
+pub struct MergedSurvey {
+    /// The chosen stations sorted by depth, in the inclination convention of the input
+    pub stations: Vec<BHOrientation>,
+    pub disagreements: Vec<SurveyDisagreement>,
+}
+
+/// A survey of one type, sorted by depth with inclinations in the `NegativeDown` convention
+struct SurveySet {
+    survey_type: Option<String>,
+    stations: Vec<BHOrientation>,
+    /// The `NegativeDown` copies of `stations` used for comparisons
+    normalised: Vec<BHOrientation>,
+}
+
+impl SurveySet {
+    fn covers(&self, depth: f64) -> bool {
+        match (self.normalised.first(), self.normalised.last()) {
+            (Some(first), Some(last)) => first.depth <= depth && depth <= last.depth,
+            _ => false,
+        }
+    }
+
+    /// The direction of the hole at a depth the survey covers, interpolated along the arc between stations
+    fn direction_at(&self, depth: f64) -> na::Vector3<f64> {
+        let next = self
+            .normalised
+            .iter()
+            .position(|station| station.depth >= depth)
+            .unwrap();
+        let end = &self.normalised[next];
+        if next == 0 || end.depth == depth {
+            return direction(end);
+        }
+        let start = &self.normalised[next - 1];
+        let fraction = (depth - start.depth) / (end.depth - start.depth);
+        slerp(&direction(start), &direction(end), fraction).0
+    }
+}
+
+/// Merge the stations of several surveys of one hole, grouped by `survey_type`.
+/// A survey is used over the depths from its first to its last station, where no survey earlier in
+/// `priority` covers the depth. Types missing from `priority` come after it in alphabetical order,
+/// stations without a type come last. An empty `priority` uses `DEFAULT_SURVEY_PRIORITY`.
+///
+/// Every station that is overridden is compared with the chosen survey at its depth.
+pub fn merge_surveys(
+    stations: Vec<BHOrientation>,
+    priority: &[String],
+    inclination_convention: InclinationConvention,
+) -> Result<MergedSurvey, String> {
+    let default_priority = DEFAULT_SURVEY_PRIORITY.map(String::from);
+    let priority = match priority.is_empty() {
+        true => &default_priority[..],
+        false => priority,
+    };
+    let mut by_type: BTreeMap<(usize, Option<String>), Vec<BHOrientation>> = BTreeMap::new();
+    for station in stations {
+        let rank = match &station.survey_type {
+            Some(survey_type) => priority
+                .iter()
+                .position(|trusted| trusted.eq_ignore_ascii_case(survey_type))
+                .unwrap_or(priority.len()),
+            None => usize::MAX,
+        };
+        by_type
+            .entry((rank, station.survey_type.clone()))
+            .or_default()
+            .push(station);
+    }
+    let sets = by_type
+        .into_iter()
+        .map(|((_, survey_type), mut stations)| {
+            stations.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+            let normalised = stations
+                .iter()
+                .map(|station| {
+                    Ok(BHOrientation {
+                        inclination: inclination_convention
+                            .try_to_negative_down(station.inclination)
+                            .map_err(|error| {
+                                format!("Survey inclination at {}: {error}", station.depth)
+                            })?,
+                        ..station.clone()
+                    })
+                })
+                .collect::<Result<Vec<BHOrientation>, String>>()?;
+            Ok(SurveySet {
+                survey_type,
+                stations,
+                normalised,
+            })
+        })
+        .collect::<Result<Vec<SurveySet>, String>>()?;
+
+    let mut merged = vec![];
+    let mut disagreements = vec![];
+    for (index, set) in sets.iter().enumerate() {
+        for (station, normalised) in set.stations.iter().zip(&set.normalised) {
+            let Some(chosen) = sets[..index]
+                .iter()
+                .find(|chosen| chosen.covers(station.depth))
+            else {
+                merged.push(station.clone());
+                continue;
+            };
+            let chosen_direction = chosen.direction_at(station.depth);
+            // Up-holes keep their upward direction, unlike a trend and plunge
+            let chosen_bearing =
+                normalise_azimuth(chosen_direction.x.atan2(chosen_direction.y).to_degrees());
+            let chosen_inclination = chosen_direction.z.clamp(-1.0, 1.0).asin().to_degrees();
+            disagreements.push(SurveyDisagreement {
+                depth: station.depth,
+                survey_type: set.survey_type.clone(),
+                chosen_type: chosen.survey_type.clone(),
+                bearing_difference: (normalised.bearing - chosen_bearing + 180.0).rem_euclid(360.0)
+                    - 180.0,
+                inclination_difference: normalised.inclination - chosen_inclination,
+                angle: direction(normalised).angle(&chosen_direction).to_degrees(),
+            });
+        }
+    }
+    merged.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+    Ok(MergedSurvey {
+        stations: merged,
+        disagreements,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn station(survey_type: &str, depth: f64, bearing: f64, inclination: f64) -> BHOrientation {
+        BHOrientation {
+            survey_type: Some(survey_type.to_string()),
+            ..test_utils::station(depth, bearing, inclination)
+        }
+    }
+
+    fn depths_and_types(merged: &MergedSurvey) -> Vec<(f64, &str)> {
+        merged
+            .stations
+            .iter()
+            .map(|station| (station.depth, station.survey_type.as_deref().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn merge_surveys_by_priority() {
+        let stations = vec![
+            station("magnetic", 30.0, 121.0, -60.0),
+            station("planned", 0.0, 120.0, -60.0),
+            station("gyro", 10.0, 118.0, -60.0),
+            station("magnetic", 60.0, 123.0, -61.0),
+            station("gyro", 50.0, 118.0, -62.0),
+            station("magnetic", 90.0, 124.0, -61.0),
+        ];
+        let merged = merge_surveys(stations, &[], InclinationConvention::NegativeDown).unwrap();
+        // The gyro covers 10 to 50 m, the magnetic survey is used below it and the plan above it
+        assert_eq!(
+            depths_and_types(&merged),
+            vec![
+                (0.0, "planned"),
+                (10.0, "gyro"),
+                (50.0, "gyro"),
+                (60.0, "magnetic"),
+                (90.0, "magnetic")
+            ]
+        );
+        assert_eq!(merged.disagreements.len(), 1);
+        let disagreement = &merged.disagreements[0];
+        assert_eq!(disagreement.survey_type.as_deref(), Some("magnetic"));
+        assert_eq!(disagreement.chosen_type.as_deref(), Some("gyro"));
+        assert!((disagreement.bearing_difference - 3.0).abs() < 1e-9);
+        // Half way between the gyro stations the inclination is close to -61°
+        assert!((disagreement.inclination_difference - 1.0).abs() < 0.01);
+        assert!(disagreement.angle > 1.0 && disagreement.angle < 3.0);
+
+        // A custom priority trusts the magnetic survey over the gyro
+        let stations = vec![
+            station("gyro", 0.0, 118.0, -60.0),
+            station("gyro", 50.0, 118.0, -62.0),
+            station("magnetic", 30.0, 121.0, -60.0),
+        ];
+        let priority = ["magnetic".to_string(), "gyro".to_string()];
+        let merged =
+            merge_surveys(stations, &priority, InclinationConvention::NegativeDown).unwrap();
+        assert_eq!(
+            depths_and_types(&merged),
+            vec![(0.0, "gyro"), (30.0, "magnetic"), (50.0, "gyro")]
+        );
+        assert!(merged.disagreements.is_empty());
+    }
+
+    #[test]
+    fn merge_surveys_conventions() {
+        // Zenith angles are compared in the negative down convention and returned as they were
+        let stations = vec![
+            station("gyro", 0.0, 359.0, 30.0),
+            station("gyro", 100.0, 359.0, 30.0),
+            station("magnetic", 50.0, 1.0, 32.0),
+        ];
+        let merged = merge_surveys(stations, &[], InclinationConvention::FromVertical).unwrap();
+        assert_eq!(merged.stations[1].inclination, 30.0);
+        let disagreement = &merged.disagreements[0];
+        assert!((disagreement.bearing_difference - 2.0).abs() < 1e-9);
+        assert!((disagreement.inclination_difference - 2.0).abs() < 1e-9);
+
+        let invalid = vec![station("gyro", 0.0, 10.0, 200.0)];
+        assert!(merge_surveys(invalid, &[], InclinationConvention::FromVertical).is_err());
+
+        // Untyped stations are used where no typed survey covers the depth
+        let mut untyped = station("", 0.0, 10.0, -60.0);
+        untyped.survey_type = None;
+        let stations = vec![
+            untyped,
+            station("unknown", 0.0, 12.0, -60.0),
+            station("unknown", 20.0, 12.0, -60.0),
+        ];
+        let merged = merge_surveys(stations, &[], InclinationConvention::NegativeDown).unwrap();
+        assert_eq!(merged.stations.len(), 2);
+        assert_eq!(merged.disagreements[0].survey_type, None);
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{
+    borehole::BHOrientation,
+    desurvey::{direction, slerp},
+};
+
+/// Dogleg severity is reported in degrees per this course length in metres
+pub const DOGLEG_COURSE_LENGTH: f64 = 30.0;
+
+/// Bearings of stations this steep (in degrees) are unstable and are not checked for jumps
+const NEAR_VERTICAL: f64 = 85.0;
+
+/// The limits above which survey stations are flagged, angles in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SurveyQaLimits {
+    /// The largest dogleg severity in degrees per 30 m
+    pub dogleg_severity: f64,
+    /// The largest bearing change from the previous station
+    pub bearing_jump: f64,
+    /// The largest inclination change from the previous station
+    pub inclination_jump: f64,
+    /// The largest angle between a station and the trend interpolated from its neighbours
+    pub trend_deviation: f64,
+}
+
+impl Default for SurveyQaLimits {
+    fn default() -> Self {
+        Self {
+            dogleg_severity: 3.0,
+            bearing_jump: 5.0,
+            inclination_jump: 3.0,
+            trend_deviation: 2.0,
+        }
+    }
+}
+
+/// Why a survey station is suspect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SurveyFlag {
+    /// The hole bends faster than a drill string can, see `SurveyQaLimits::dogleg_severity`
+    DoglegSeverity,
+    BearingJump,
+    InclinationJump,
+    /// The station disagrees with its neighbours, e.g. a magnetic reading disturbed by massive sulphides
+    TrendOutlier,
+}
+
+impl fmt::Display for SurveyFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DoglegSeverity => write!(f, "dogleg-severity"),
+            Self::BearingJump => write!(f, "bearing-jump"),
+            Self::InclinationJump => write!(f, "inclination-jump"),
+            Self::TrendOutlier => write!(f, "trend-outlier"),
+        }
+    }
+}
+
+/// The checks of one survey station. Changes are from the previous station and are `None` for the first.
+#[derive(Debug, Clone, Serialize)]
+pub struct StationQa {
+    pub depth: f64,
+    pub bearing: f64,
+    pub inclination: f64,
+    /// The dogleg severity from the previous station in degrees per 30 m
+    pub dogleg_severity: Option<f64>,
+    /// The signed bearing change from the previous station, between -180° and 180°
+    pub bearing_change: Option<f64>,
+    pub inclination_change: Option<f64>,
+    /// The angle between the station and the direction interpolated from its neighbours,
+    /// `None` for the first and last station
+    pub trend_deviation: Option<f64>,
+    pub flags: Vec<SurveyFlag>,
+}
+
+impl StationQa {
+    pub fn is_flagged(&self) -> bool {
+        !self.flags.is_empty()
+    }
+}
+
+/// Check a survey for doglegs, jumps and stations that disagree with their neighbours.
+/// A station is a trend outlier when it deviates more than its neighbours from the trend interpolated
+/// between the stations either side of it.
+/// `stations` must be sorted by depth and use the `NegativeDown` inclination convention.
+/// Stations at the same depth are compared by angle only.
+pub fn survey_qa(stations: &[BHOrientation], limits: &SurveyQaLimits) -> Vec<StationQa> {
+    let directions = stations.iter().map(direction).collect::<Vec<_>>();
+    let mut report = stations
+        .iter()
+        .enumerate()
+        .map(|(index, station)| {
+            let mut qa = StationQa {
+                depth: station.depth,
+                bearing: station.bearing,
+                inclination: station.inclination,
+                dogleg_severity: None,
+                bearing_change: None,
+                inclination_change: None,
+                trend_deviation: None,
+                flags: vec![],
+            };
+            if index > 0 {
+                let previous = &stations[index - 1];
+                let dogleg = directions[index - 1].angle(&directions[index]).to_degrees();
+                let length = station.depth - previous.depth;
+                let severity = match length > 0.0 {
+                    true => dogleg * DOGLEG_COURSE_LENGTH / length,
+                    false if dogleg > 0.0 => f64::INFINITY,
+                    false => 0.0,
+                };
+                let bearing_change =
+                    (station.bearing - previous.bearing + 180.0).rem_euclid(360.0) - 180.0;
+                let inclination_change = station.inclination - previous.inclination;
+                if severity > limits.dogleg_severity {
+                    qa.flags.push(SurveyFlag::DoglegSeverity);
+                }
+                let near_vertical =
+                    previous.inclination.abs().min(station.inclination.abs()) >= NEAR_VERTICAL;
+                if bearing_change.abs() > limits.bearing_jump && !near_vertical {
+                    qa.flags.push(SurveyFlag::BearingJump);
+                }
+                if inclination_change.abs() > limits.inclination_jump {
+                    qa.flags.push(SurveyFlag::InclinationJump);
+                }
+                qa.dogleg_severity = Some(severity);
+                qa.bearing_change = Some(bearing_change);
+                qa.inclination_change = Some(inclination_change);
+            }
+            if index > 0 && index + 1 < stations.len() {
+                let (previous, next) = (&stations[index - 1], &stations[index + 1]);
+                let span = next.depth - previous.depth;
+                let fraction = match span > 0.0 {
+                    true => (station.depth - previous.depth) / span,
+                    false => 0.5,
+                };
+                let (trend, _) = slerp(&directions[index - 1], &directions[index + 1], fraction);
+                qa.trend_deviation = Some(trend.angle(&directions[index]).to_degrees());
+            }
+            qa
+        })
+        .collect::<Vec<StationQa>>();
+
+    // A bad station also pulls the trend of its neighbours, only the worst of them is the outlier
+    let deviations = report
+        .iter()
+        .map(|station| station.trend_deviation.unwrap_or(0.0))
+        .collect::<Vec<f64>>();
+    for (index, station) in report.iter_mut().enumerate() {
+        let deviation = deviations[index];
+        let neighbours = &deviations[index.saturating_sub(1)..(index + 2).min(deviations.len())];
+        if deviation > limits.trend_deviation
+            && neighbours.iter().all(|&neighbour| neighbour <= deviation)
+        {
+            station.flags.push(SurveyFlag::TrendOutlier);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::station;
+
+    #[test]
+    fn survey_qa_smooth_hole() {
+        // 1° of lift every 30 m
+        let stations = (0..10)
+            .map(|i| station(i as f64 * 30.0, 45.0, -60.0 + i as f64))
+            .collect::<Vec<BHOrientation>>();
+        let qa = survey_qa(&stations, &SurveyQaLimits::default());
+        assert_eq!(qa.len(), 10);
+        assert!(qa.iter().all(|station| !station.is_flagged()));
+        assert!(qa[0].dogleg_severity.is_none() && qa[0].trend_deviation.is_none());
+        assert!((qa[1].dogleg_severity.unwrap() - 1.0).abs() < 1e-9);
+        assert!(qa[5].trend_deviation.unwrap() < 1e-6);
+        assert!(qa[9].trend_deviation.is_none());
+    }
+
+    #[test]
+    fn survey_qa_magnetic_interference() {
+        let mut stations = (0..7)
+            .map(|i| station(i as f64 * 30.0, 120.0, -70.0))
+            .collect::<Vec<BHOrientation>>();
+        // A disturbed magnetic reading
+        stations[3].bearing = 135.0;
+        let qa = survey_qa(&stations, &SurveyQaLimits::default());
+        assert_eq!(
+            qa[3].flags,
+            vec![
+                SurveyFlag::DoglegSeverity,
+                SurveyFlag::BearingJump,
+                SurveyFlag::TrendOutlier
+            ]
+        );
+        assert_eq!(qa[3].bearing_change, Some(15.0));
+        assert_eq!(qa[4].bearing_change, Some(-15.0));
+        // The next station jumps back but agrees with the trend of its neighbours
+        assert!(qa[4].flags.contains(&SurveyFlag::BearingJump));
+        assert!(!qa[4].flags.contains(&SurveyFlag::TrendOutlier));
+        assert!(!qa[2].is_flagged() && !qa[5].is_flagged());
+    }
+
+    #[test]
+    fn survey_qa_wraps_bearings() {
+        let stations = [
+            station(0.0, 359.0, -89.0),
+            station(30.0, 1.0, -60.0),
+            station(60.0, 100.0, -89.5),
+        ];
+        let qa = survey_qa(&stations, &SurveyQaLimits::default());
+        assert!((qa[1].bearing_change.unwrap() - 2.0).abs() < 1e-9);
+        assert!(qa[1].flags.contains(&SurveyFlag::InclinationJump));
+        assert!(!qa[1].flags.contains(&SurveyFlag::BearingJump));
+        // The bearing of near vertical stations is not checked
+        let stations = [station(0.0, 10.0, -89.0), station(30.0, 100.0, -89.5)];
+        let qa = survey_qa(&stations, &SurveyQaLimits::default());
+        assert!(!qa[1].flags.contains(&SurveyFlag::BearingJump));
+    }
+}
@@ -0,0 +1,179 @@
+use na::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::{borehole::BHOrientation, desurvey::direction, utils::normalise_azimuth};
+
+/// How survey stations are smoothed before measurements are oriented with them
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SurveySmoothing {
+    /// The number of stations the median is taken over, centred on each station.
+    /// Even windows are widened by one station and the smallest window is 3.
+    pub window: usize,
+    /// Stations further than this angle (in degrees) from the median are removed. `None` keeps every station.
+    pub rejection_angle: Option<f64>,
+}
+
+impl Default for SurveySmoothing {
+    fn default() -> Self {
+        Self {
+            window: 5,
+            rejection_angle: Some(3.0),
+        }
+    }
+}
+
+/// What smoothing did to a survey station
+#[derive(Debug, Clone, Serialize)]
+pub struct SmoothedStation {
+    pub depth: f64,
+    /// The bearing before smoothing
+    pub bearing: f64,
+    /// The inclination before smoothing, in the `NegativeDown` convention
+    pub inclination: f64,
+    pub smoothed_bearing: f64,
+    pub smoothed_inclination: f64,
+    /// The angle in degrees between the station and its smoothed direction
+    pub change: f64,
+    /// The station was removed from the survey. The first station is kept with its smoothed direction
+    /// so that the survey still starts at the collar.
+    pub rejected: bool,
+}
+
+/// Smooth a survey with a median filter on the direction of the hole.
+/// The median is taken of each component of the direction vectors in the window, which a few bad stations
+/// cannot pull away from the good ones. The first and last stations are smoothed with their two nearest
+/// stations, which pulls them towards the inside of a curve.
+/// `stations` must be sorted by depth and use the `NegativeDown` inclination convention.
+///
+/// Returns the smoothed stations and what happened to every station.
+pub fn smooth_survey(
+    stations: &[BHOrientation],
+    smoothing: &SurveySmoothing,
+) -> (Vec<BHOrientation>, Vec<SmoothedStation>) {
+    let directions = stations
+        .iter()
+        .map(direction)
+        .collect::<Vec<Vector3<f64>>>();
+    let half = (smoothing.window / 2).max(1);
+    let mut smoothed = vec![];
+    let mut report = vec![];
+    for (index, station) in stations.iter().enumerate() {
+        // The window shrinks towards the ends so that it stays centred and a steady curve is kept.
+        // The end stations are compared with their two nearest stations instead.
+        let last = directions.len() - 1;
+        let window = match half.min(index).min(last - index) {
+            0 if index == 0 => &directions[..3.min(directions.len())],
+            0 => &directions[index.saturating_sub(2)..],
+            half => &directions[index - half..=index + half],
+        };
+        let median =
+            Vector3::from_fn(|row, _| median(window.iter().map(|direction| direction[row])));
+        let median = match median.norm() > 1e-9 {
+            true => median.normalize(),
+            // Opposite directions cancel out, the station is kept as it is
+            false => directions[index],
+        };
+        let change = directions[index].angle(&median).to_degrees();
+        // Stations the median agrees with keep their values rather than their round trip through a vector
+        let (bearing, inclination) = match (median - directions[index]).norm() < 1e-12 {
+            true => (station.bearing, station.inclination),
+            false => (
+                normalise_azimuth(median.x.atan2(median.y).to_degrees()),
+                median.z.clamp(-1.0, 1.0).asin().to_degrees(),
+            ),
+        };
+        let rejected = smoothing
+            .rejection_angle
+            .is_some_and(|angle| change > angle);
+        if !rejected || index == 0 {
+            smoothed.push(BHOrientation {
+                bearing,
+                inclination,
+                ..station.clone()
+            });
+        }
+        report.push(SmoothedStation {
+            depth: station.depth,
+            bearing: station.bearing,
+            inclination: station.inclination,
+            smoothed_bearing: bearing,
+            smoothed_inclination: inclination,
+            change,
+            rejected,
+        });
+    }
+    (smoothed, report)
+}
+
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values = values.collect::<Vec<f64>>();
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    match values.len() % 2 {
+        0 => (values[middle - 1] + values[middle]) / 2.0,
+        _ => values[middle],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::station;
+
+    #[test]
+    fn smooth_survey_rejects_outliers() {
+        let mut stations = (0..8)
+            .map(|i| station(i as f64 * 30.0, 120.0, -70.0))
+            .collect::<Vec<BHOrientation>>();
+        stations[3].bearing = 150.0;
+        let (smoothed, report) = smooth_survey(&stations, &SurveySmoothing::default());
+        assert_eq!(smoothed.len(), 7);
+        assert!(smoothed.iter().all(|station| station.depth != 90.0));
+        assert!(smoothed
+            .iter()
+            .all(|station| (station.bearing - 120.0).abs() < 1e-9
+                && (station.inclination + 70.0).abs() < 1e-9));
+        let rejected = report
+            .iter()
+            .filter(|station| station.rejected)
+            .collect::<Vec<&SmoothedStation>>();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!((rejected[0].depth, rejected[0].bearing), (90.0, 150.0));
+        assert!(rejected[0].change > 9.0);
+        assert!((rejected[0].smoothed_bearing - 120.0).abs() < 1e-9);
+
+        // Without rejection the outlier is replaced by its smoothed direction
+        let smoothing = SurveySmoothing {
+            rejection_angle: None,
+            ..Default::default()
+        };
+        let (smoothed, report) = smooth_survey(&stations, &smoothing);
+        assert_eq!(smoothed.len(), 8);
+        assert!((smoothed[3].bearing - 120.0).abs() < 1e-9);
+        assert!(report.iter().all(|station| !station.rejected));
+    }
+
+    #[test]
+    fn smooth_survey_keeps_curves_and_the_collar() {
+        // A steady curve is left as it is by the median
+        let stations = (0..6)
+            .map(|i| station(i as f64 * 30.0, 100.0 + i as f64, -60.0))
+            .collect::<Vec<BHOrientation>>();
+        let (smoothed, report) = smooth_survey(&stations, &SurveySmoothing::default());
+        assert_eq!(smoothed.len(), 6);
+        assert!(report[1..5].iter().all(|station| station.change < 0.1));
+
+        // A bad collar station is replaced rather than removed
+        let mut stations = stations;
+        stations[0].bearing = 140.0;
+        let (smoothed, report) = smooth_survey(&stations, &SurveySmoothing::default());
+        assert!(report[0].rejected);
+        assert_eq!(smoothed[0].depth, 0.0);
+        // The median of the bad station and the two below it
+        assert!((smoothed[0].bearing - 102.0).abs() < 1e-9);
+
+        let (smoothed, _) = smooth_survey(&stations[..1], &SurveySmoothing::default());
+        assert_eq!(smoothed[0].bearing, 140.0);
+    }
+}
@@ -0,0 +1,12 @@
+use crate::borehole::BHOrientation;
+
+/// A survey station without a hole id or survey type, inclination in the `NegativeDown` convention
+pub(crate) fn station(depth: f64, bearing: f64, inclination: f64) -> BHOrientation {
+    BHOrientation {
+        hole_id: None,
+        survey_type: None,
+        depth,
+        bearing,
+        inclination,
+    }
+}
@@ -0,0 +1,403 @@
+use na::{Matrix2, Matrix3, Matrix3x4, Matrix4, Vector3};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    f64::consts::{FRAC_PI_2, PI},
+    fmt,
+};
+
+use crate::{
+    borehole::Orient,
+    structure::Lineation,
+    utils::vector::{perpendicular_basis, trend_and_plunge_from_vector},
+};
+
+/// Chi-squared value with two degrees of freedom at 95% confidence.
+/// Scales the variance of a two dimensional normal distribution to its 95% confidence ellipse.
+const CHI_SQUARED_95_2D: f64 = 5.991464547107979;
+
+/// One standard deviation of the error (in degrees) on each input used to orient a measurement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct MeasurementErrors {
+    pub bearing: f64,
+    pub inclination: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+/// The 95% confidence ellipse around a pole on the unit sphere.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ErrorEllipse {
+    /// The semi-major axis of the ellipse in degrees
+    pub major: f64,
+    /// The semi-minor axis of the ellipse in degrees
+    pub minor: f64,
+    /// The direction of the major axis, tangent to the sphere at the pole
+    pub major_axis: Lineation,
+}
+
+/// The uncertainty in the orientation of a pole.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PoleUncertainty {
+    /// The radius (in degrees) of the cone around the pole that contains 95% of the possible poles
+    pub cone_95: f64,
+    pub ellipse: ErrorEllipse,
+}
+
+/// An input used to orient a measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrientInput {
+    Bearing,
+    Inclination,
+    Alpha,
+    Beta,
+}
+
+impl fmt::Display for OrientInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bearing => write!(f, "bearing"),
+            Self::Inclination => write!(f, "inclination"),
+            Self::Alpha => write!(f, "alpha"),
+            Self::Beta => write!(f, "beta"),
+        }
+    }
+}
+
+/// How far (in degrees) the pole rotates for a one degree change in each input.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Sensitivities {
+    pub bearing: f64,
+    pub inclination: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    /// The input that contributes the most to the uncertainty of the pole, given the input errors
+    pub dominant: OrientInput,
+}
+
+/// The first order uncertainty of a pole propagated from the errors on its inputs.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PoleCovariance {
+    /// The covariance (in radians²) of the unit pole vector in global east, north, up coordinates
+    pub covariance: [[f64; 3]; 3],
+    pub uncertainty: PoleUncertainty,
+    pub sensitivities: Sensitivities,
+}
+
+impl Orient {
+    /// Estimate the uncertainty of the pole by sampling `samples` perturbed copies of the inputs.
+    /// Each input is perturbed by a normally distributed error with the standard deviation in `errors`.
+    /// The same `seed` always gives the same result.
+    pub fn monte_carlo(
+        &self,
+        errors: &MeasurementErrors,
+        samples: usize,
+        seed: u64,
+    ) -> PoleUncertainty {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let nominal = self.normal_g().normalize();
+
+        let poles = (0..samples.max(1))
+            .map(|_| {
+                let perturbed = Orient {
+                    bearing: self.bearing + standard_normal(&mut rng) * errors.bearing.to_radians(),
+                    inclination: self.inclination
+                        + standard_normal(&mut rng) * errors.inclination.to_radians(),
+                    alpha: self.alpha + standard_normal(&mut rng) * errors.alpha.to_radians(),
+                    beta: self.beta + standard_normal(&mut rng) * errors.beta.to_radians(),
+                };
+                // Poles are lines, keep every sample on the same side as the nominal pole
+                let pole = perturbed.normal_g().normalize();
+                if pole.dot(&nominal) < 0.0 {
+                    -pole
+                } else {
+                    pole
+                }
+            })
+            .collect::<Vec<Vector3<f64>>>();
+
+        let mean = poles.iter().sum::<Vector3<f64>>().normalize();
+        let mut angles = poles
+            .iter()
+            .map(|pole| pole.dot(&mean).clamp(-1.0, 1.0).acos().to_degrees())
+            .collect::<Vec<f64>>();
+        angles.sort_by(f64::total_cmp);
+        let cone_95 =
+            angles[((angles.len() as f64 * 0.95).ceil() as usize).clamp(1, angles.len()) - 1];
+
+        // Covariance of the sampled poles projected onto the plane tangent to the mean pole
+        let (u, v) = perpendicular_basis(&mean);
+        let covariance = poles.iter().fold(Matrix2::zeros(), |covariance, pole| {
+            let offset = pole - mean;
+            let (x, y) = (offset.dot(&u), offset.dot(&v));
+            covariance + Matrix2::new(x * x, x * y, x * y, y * y)
+        }) / poles.len() as f64;
+
+        PoleUncertainty {
+            cone_95,
+            ellipse: error_ellipse(&covariance, &u, &v),
+        }
+    }
+
+    /// Propagate the input errors through the rotation to the pole to first order.
+    /// Much faster than `monte_carlo`, but it assumes the pole varies linearly over the size of the errors.
+    pub fn propagate_errors(&self, errors: &MeasurementErrors) -> PoleCovariance {
+        let jacobian = self.jacobian();
+        let variances = Matrix4::from_diagonal(&na::Vector4::new(
+            errors.bearing.to_radians().powi(2),
+            errors.inclination.to_radians().powi(2),
+            errors.alpha.to_radians().powi(2),
+            errors.beta.to_radians().powi(2),
+        ));
+        let covariance: Matrix3<f64> = jacobian * variances * jacobian.transpose();
+
+        let pole = self.normal_g().normalize();
+        let (u, v) = perpendicular_basis(&pole);
+        let tangent = Matrix2::new(
+            u.dot(&(covariance * u)),
+            u.dot(&(covariance * v)),
+            v.dot(&(covariance * u)),
+            v.dot(&(covariance * v)),
+        );
+
+        // The pole is a unit vector, so the length of each derivative is the angle it rotates by
+        let rates = jacobian
+            .column_iter()
+            .map(|column| column.norm())
+            .collect::<Vec<f64>>();
+        let contributions = [
+            (OrientInput::Bearing, rates[0] * errors.bearing),
+            (OrientInput::Inclination, rates[1] * errors.inclination),
+            (OrientInput::Alpha, rates[2] * errors.alpha),
+            (OrientInput::Beta, rates[3] * errors.beta),
+        ];
+        let dominant = contributions
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(input, _)| *input)
+            .unwrap();
+
+        PoleCovariance {
+            covariance: covariance.transpose().into(),
+            uncertainty: PoleUncertainty {
+                cone_95: cone_95_from_covariance(&tangent),
+                ellipse: error_ellipse(&tangent, &u, &v),
+            },
+            sensitivities: Sensitivities {
+                bearing: rates[0],
+                inclination: rates[1],
+                alpha: rates[2],
+                beta: rates[3],
+                dominant,
+            },
+        }
+    }
+
+    /// The derivatives of the global normal with respect to bearing, inclination, alpha and beta (in that order).
+    fn jacobian(&self) -> Matrix3x4<f64> {
+        let (alpha, beta) = (self.alpha, self.beta);
+        let z_rot = self.z_rot();
+        let y_rot = self.y_rot();
+        let normal_bh = self.normal_bh();
+
+        // The rotation angles are π/2 - bearing and π/2 - inclination, hence the negative signs
+        let b = FRAC_PI_2 - self.bearing;
+        let d_z_rot = Matrix3::new(
+            -b.sin(),
+            -b.cos(),
+            0.0,
+            b.cos(),
+            -b.sin(),
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+        );
+        let i = FRAC_PI_2 - self.inclination;
+        let d_y_rot = Matrix3::new(
+            -i.sin(),
+            0.0,
+            i.cos(),
+            0.0,
+            0.0,
+            0.0,
+            -i.cos(),
+            0.0,
+            -i.sin(),
+        );
+
+        let d_alpha = Vector3::new(
+            -alpha.sin() * beta.cos(),
+            -alpha.sin() * beta.sin(),
+            alpha.cos(),
+        );
+        let d_beta = Vector3::new(-alpha.cos() * beta.sin(), alpha.cos() * beta.cos(), 0.0);
+
+        Matrix3x4::from_columns(&[
+            -d_z_rot * y_rot * normal_bh,
+            -z_rot * d_y_rot * normal_bh,
+            z_rot * y_rot * d_alpha,
+            z_rot * y_rot * d_beta,
+        ])
+    }
+}
+
+/// The radius (in degrees) of the circle that contains 95% of a two dimensional normal distribution
+/// with the given covariance (in radians²).
+fn cone_95_from_covariance(covariance: &Matrix2<f64>) -> f64 {
+    let (a, b, c) = (covariance[(0, 0)], covariance[(0, 1)], covariance[(1, 1)]);
+    let mean = (a + c) / 2.0;
+    let spread = (((a - c) / 2.0).powi(2) + b.powi(2)).sqrt();
+    let major = mean + spread;
+    if major <= 0.0 {
+        return 0.0;
+    }
+    // Avoid dividing by zero when the distribution collapses to a line
+    let minor = (mean - spread).max(major * 1e-12);
+
+    // Probability that a sample falls within `radius`, integrated around the circle
+    let steps = 360;
+    let probability = |radius: f64| -> f64 {
+        (0..steps)
+            .map(|step| {
+                let theta = 2.0 * PI * (step as f64 + 0.5) / steps as f64;
+                let q = theta.cos().powi(2) / major + theta.sin().powi(2) / minor;
+                (1.0 - (-radius.powi(2) * q / 2.0).exp()) / q
+            })
+            .sum::<f64>()
+            / (steps as f64 * (major * minor).sqrt())
+    };
+
+    // The 95% radius is between the circular radii of the minor and major axes
+    let mut low = 0.0;
+    let mut high = (major * CHI_SQUARED_95_2D).sqrt() * 2.0;
+    for _ in 0..60 {
+        let radius = (low + high) / 2.0;
+        if probability(radius) < 0.95 {
+            low = radius;
+        } else {
+            high = radius;
+        }
+    }
+    ((low + high) / 2.0).to_degrees()
+}
+
+/// The 95% confidence ellipse of a covariance matrix (in radians²) expressed in the tangent basis `u`, `v`.
+pub(crate) fn error_ellipse(
+    covariance: &Matrix2<f64>,
+    u: &Vector3<f64>,
+    v: &Vector3<f64>,
+) -> ErrorEllipse {
+    let (a, b, c) = (covariance[(0, 0)], covariance[(0, 1)], covariance[(1, 1)]);
+    let mean = (a + c) / 2.0;
+    let spread = (((a - c) / 2.0).powi(2) + b.powi(2)).sqrt();
+    let angle = 0.5 * (2.0 * b).atan2(a - c);
+
+    let (trend, plunge) = trend_and_plunge_from_vector(&(u * angle.cos() + v * angle.sin()));
+
+    ErrorEllipse {
+        major: ((mean + spread).max(0.0) * CHI_SQUARED_95_2D)
+            .sqrt()
+            .to_degrees(),
+        minor: ((mean - spread).max(0.0) * CHI_SQUARED_95_2D)
+            .sqrt()
+            .to_degrees(),
+        major_axis: Lineation::new(trend.to_degrees(), plunge.to_degrees()),
+    }
+}
+
+/// A sample from the standard normal distribution using the Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    // Shift to (0, 1] so the logarithm is finite
+    let u1 = 1.0 - rng.gen::<f64>();
+    let u2 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BHOrientationLine;
+
+    const ERRORS: MeasurementErrors = MeasurementErrors {
+        bearing: 2.0,
+        inclination: 1.0,
+        alpha: 3.0,
+        beta: 5.0,
+    };
+
+    #[test]
+    fn monte_carlo_is_deterministic() {
+        let orient = Orient::new(262.7, -55.3, 65.0, 230.0, BHOrientationLine::Top);
+        let first = orient.monte_carlo(&ERRORS, 500, 42);
+        let second = orient.monte_carlo(&ERRORS, 500, 42);
+
+        assert_eq!(first.cone_95, second.cone_95);
+        assert_eq!(first.ellipse.major, second.ellipse.major);
+        assert!(first.cone_95 > 0.0);
+        assert!(first.ellipse.major >= first.ellipse.minor);
+    }
+
+    #[test]
+    fn monte_carlo_without_errors() {
+        let orient = Orient::new(262.7, -55.3, 65.0, 230.0, BHOrientationLine::Top);
+        let uncertainty = orient.monte_carlo(&MeasurementErrors::default(), 100, 0);
+
+        assert!(uncertainty.cone_95 < 1e-6);
+        assert!(uncertainty.ellipse.major < 1e-6);
+    }
+
+    #[test]
+    fn monte_carlo_low_alpha_is_sensitive_to_beta() {
+        let beta_only = MeasurementErrors {
+            beta: 5.0,
+            ..Default::default()
+        };
+        let low_alpha = Orient::new(262.7, -55.3, 10.0, 230.0, BHOrientationLine::Top)
+            .monte_carlo(&beta_only, 1000, 7);
+        let high_alpha = Orient::new(262.7, -55.3, 80.0, 230.0, BHOrientationLine::Top)
+            .monte_carlo(&beta_only, 1000, 7);
+
+        assert!(low_alpha.cone_95 > 4.0 * high_alpha.cone_95);
+    }
+
+    #[test]
+    fn propagate_errors_matches_monte_carlo() {
+        let orient = Orient::new(262.7, -55.3, 65.0, 230.0, BHOrientationLine::Top);
+        let analytical = orient.propagate_errors(&ERRORS).uncertainty;
+        let monte_carlo = orient.monte_carlo(&ERRORS, 20000, 3);
+
+        assert!((analytical.cone_95 - monte_carlo.cone_95).abs() / monte_carlo.cone_95 < 0.05);
+        assert!(
+            (analytical.ellipse.major - monte_carlo.ellipse.major).abs()
+                / monte_carlo.ellipse.major
+                < 0.05
+        );
+    }
+
+    #[test]
+    fn propagate_errors_sensitivities() {
+        let orient = Orient::new(262.7, -55.3, 10.0, 230.0, BHOrientationLine::Top);
+        let sensitivities = orient.propagate_errors(&ERRORS).sensitivities;
+
+        // A degree of alpha always rotates the pole by a degree, beta by cos(alpha) degrees
+        assert!((sensitivities.alpha - 1.0).abs() < 1e-9);
+        assert!((sensitivities.beta - 10.0_f64.to_radians().cos()).abs() < 1e-9);
+        assert_eq!(sensitivities.dominant, OrientInput::Beta);
+
+        let orient = Orient::new(262.7, -55.3, 85.0, 230.0, BHOrientationLine::Top);
+        let sensitivities = orient.propagate_errors(&ERRORS).sensitivities;
+        assert_eq!(sensitivities.dominant, OrientInput::Alpha);
+    }
+
+    #[test]
+    fn propagate_errors_without_errors() {
+        let orient = Orient::new(262.7, -55.3, 65.0, 230.0, BHOrientationLine::Top);
+        let propagated = orient.propagate_errors(&MeasurementErrors::default());
+
+        assert_eq!(propagated.uncertainty.cone_95, 0.0);
+        assert_eq!(propagated.uncertainty.ellipse.major, 0.0);
+    }
+}
@@ -0,0 +1,341 @@
+use na::Vector3;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+use crate::{
+    borehole::BHOrientation,
+    structure::{Lineation, Plane},
+    utils::vector::{
+        hole_axis, perpendicular_basis, trend_and_plunge_from_vector, vector_from_trend_and_plunge,
+    },
+    validation::error_if_out_of_range,
+};
+
+/// The small circle (cone) of possible poles to a plane measured in unoriented core.
+/// Without beta the plane can be rotated freely about the hole axis, so every pole at
+/// `half_angle` from the axis is consistent with the measured alpha.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AlphaCone {
+    /// The hole axis at the measurement depth as a downward pointing line.
+    /// For up-holes this is the hole trajectory reversed.
+    pub axis: Lineation,
+    /// The alpha angle (in degrees) the cone was built from.
+    pub alpha: f64,
+    /// The angle (in degrees) between the hole axis and every possible pole. Equals 90° - alpha.
+    pub half_angle: f64,
+}
+
+impl AlphaCone {
+    /// Create the cone of possible poles from the hole bearing and inclination (in the `NegativeDown`
+    /// convention) at the measurement depth and the measured alpha angle. All angles are in degrees.
+    pub fn new(bearing: f64, inclination: f64, alpha: f64) -> Self {
+        error_if_out_of_range(&bearing, 0.0, 360.0).unwrap();
+        error_if_out_of_range(&inclination, -90.0, 90.0).unwrap();
+        error_if_out_of_range(&alpha, 0.0, 90.0).unwrap();
+
+        let axis = hole_axis(bearing.to_radians(), inclination.to_radians());
+        let (trend, plunge) = trend_and_plunge_from_vector(&axis);
+
+        Self {
+            axis: Lineation::new(trend.to_degrees(), plunge.to_degrees()),
+            alpha,
+            half_angle: 90.0 - alpha,
+        }
+    }
+
+    /// Create the cone of possible poles from the survey station at the measurement depth.
+    /// See `Borehole::orientation_at`.
+    pub fn from_orientation(orientation: &BHOrientation, alpha: f64) -> Self {
+        Self::new(orientation.bearing, orientation.inclination, alpha)
+    }
+
+    /// Returns `count` poles spaced evenly around the cone, e.g. for plotting the small circle on a stereonet.
+    /// Poles are returned as downward pointing lines, so parts of the cone in the upper hemisphere are
+    /// reflected through the origin.
+    pub fn poles(&self, count: usize) -> Vec<Lineation> {
+        let axis = self.axis_vector();
+        let (u, v) = perpendicular_basis(&axis);
+        let half_angle = self.half_angle.to_radians();
+
+        (0..count)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / count as f64;
+                let pole = axis * half_angle.cos()
+                    + (u * angle.cos() + v * angle.sin()) * half_angle.sin();
+                let (trend, plunge) = trend_and_plunge_from_vector(&pole);
+                Lineation::new(trend.to_degrees(), plunge.to_degrees())
+            })
+            .collect()
+    }
+
+    /// The angle (in degrees) between a pole and the nearest point on the cone.
+    pub fn misfit(&self, pole: &Lineation) -> f64 {
+        self.vector_misfit(&vector_from_trend_and_plunge(
+            pole.trend.to_radians(),
+            pole.plunge.to_radians(),
+        ))
+    }
+
+    pub(crate) fn axis_vector(&self) -> Vector3<f64> {
+        vector_from_trend_and_plunge(self.axis.trend.to_radians(), self.axis.plunge.to_radians())
+    }
+
+    fn vector_misfit(&self, pole: &Vector3<f64>) -> f64 {
+        let angle = pole
+            .dot(&self.axis_vector())
+            .abs()
+            .clamp(0.0, 1.0)
+            .acos()
+            .to_degrees();
+        (angle - self.half_angle).abs()
+    }
+}
+
+/// A candidate orientation of a structure found from the alpha cones of several holes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConeSolution {
+    pub plane: Plane,
+    /// The root mean square angle (in degrees) between the pole and the cones.
+    /// Zero when all the cones intersect exactly.
+    pub misfit: f64,
+    /// The angle (in degrees) between the pole and each cone, in the order the cones were given.
+    pub residuals: Vec<f64>,
+}
+
+/// Smallest angle (in degrees) allowed between two hole axes for them to constrain the solution.
+const MIN_AXIS_SEPARATION: f64 = 5.0;
+/// Solutions closer than this angle (in degrees) are treated as the same solution.
+const SAME_SOLUTION_ANGLE: f64 = 0.5;
+
+/// Orient a structure from alpha angles measured where it is intersected by two or more non-parallel holes.
+///
+/// This is the stereonet method for unoriented core: the pole to the structure must lie on the cone of
+/// every hole, so it is found where the cones intersect. Each pair of cones intersects in up to four
+/// lines, which are refined against all the cones and returned ordered from the best to the worst misfit.
+/// Two holes usually give two or four equally good candidates, a third hole is needed to pick between them.
+/// Where the cones do not meet, e.g. from measurement error, the closest approach is returned with its misfit.
+pub fn solve_alpha_cones(cones: &[AlphaCone]) -> Result<Vec<ConeSolution>, String> {
+    if cones.len() < 2 {
+        return Err(format!(
+            "At least two alpha cones are needed to orient a structure, got {}",
+            cones.len()
+        ));
+    }
+
+    let mut seeds: Vec<Vector3<f64>> = vec![];
+    for (i, first) in cones.iter().enumerate() {
+        for second in &cones[i + 1..] {
+            seeds.extend(intersect_cones(first, second));
+        }
+    }
+    if seeds.is_empty() {
+        return Err(format!(
+            "The hole axes must be at least {MIN_AXIS_SEPARATION}° apart to orient a structure"
+        ));
+    }
+
+    let mut solutions: Vec<Vector3<f64>> = vec![];
+    for seed in seeds {
+        let pole = refine_pole(cones, seed);
+        let is_new = solutions
+            .iter()
+            .all(|solution| angle_between_lines(solution, &pole) > SAME_SOLUTION_ANGLE);
+        if is_new {
+            solutions.push(pole);
+        }
+    }
+
+    let mut solutions = solutions
+        .into_iter()
+        .map(|pole| {
+            let residuals = cones
+                .iter()
+                .map(|cone| cone.vector_misfit(&pole))
+                .collect::<Vec<f64>>();
+            let (trend, plunge) = trend_and_plunge_from_vector(&pole);
+
+            ConeSolution {
+                plane: Plane::from_pole(Lineation::new(trend.to_degrees(), plunge.to_degrees())),
+                misfit: rms(&residuals),
+                residuals,
+            }
+        })
+        .collect::<Vec<ConeSolution>>();
+    solutions.sort_by(|a, b| a.misfit.total_cmp(&b.misfit));
+
+    Ok(solutions)
+}
+
+/// Returns the lines where two cones intersect, or their closest approach when they do not meet.
+/// Returns nothing when the hole axes are too close to parallel.
+fn intersect_cones(first: &AlphaCone, second: &AlphaCone) -> Vec<Vector3<f64>> {
+    let a1 = first.axis_vector();
+    let a2 = second.axis_vector();
+    if angle_between_lines(&a1, &a2) < MIN_AXIS_SEPARATION {
+        return vec![];
+    }
+
+    let d = a1.dot(&a2);
+    let normal = a1.cross(&a2).normalize();
+    let c1 = first.half_angle.to_radians().cos();
+    let c2 = second.half_angle.to_radians().cos();
+
+    // Poles are lines, so only the sign of one cone needs to be flipped
+    let mut intersections = vec![];
+    for c2 in [c2, -c2] {
+        let x = (c1 - d * c2) / (1.0 - d.powi(2));
+        let y = (c2 - d * c1) / (1.0 - d.powi(2));
+        let in_plane = a1 * x + a2 * y;
+        let z_squared = 1.0 - in_plane.norm_squared();
+
+        if z_squared >= 0.0 {
+            let z = z_squared.sqrt();
+            intersections.push(in_plane + normal * z);
+            intersections.push(in_plane - normal * z);
+        } else {
+            intersections.push(in_plane.normalize());
+        }
+    }
+    intersections
+}
+
+/// Minimise the squared misfit of a pole to all cones with a pattern search on the unit sphere.
+fn refine_pole(cones: &[AlphaCone], seed: Vector3<f64>) -> Vector3<f64> {
+    let cost = |pole: &Vector3<f64>| -> f64 {
+        cones
+            .iter()
+            .map(|cone| cone.vector_misfit(pole).powi(2))
+            .sum()
+    };
+
+    let mut pole = seed.normalize();
+    let mut best = cost(&pole);
+    let mut step = 2.0_f64.to_radians();
+
+    while step > 1e-9 {
+        let (u, v) = perpendicular_basis(&pole);
+        let improved = [u, -u, v, -v]
+            .into_iter()
+            .map(|direction| (pole + direction * step.tan()).normalize())
+            .map(|candidate| (cost(&candidate), candidate))
+            .filter(|(candidate_cost, _)| *candidate_cost < best)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        match improved {
+            Some((candidate_cost, candidate)) => {
+                best = candidate_cost;
+                pole = candidate;
+            }
+            None => step /= 2.0,
+        }
+    }
+    pole
+}
+
+/// The acute angle (in degrees) between two lines.
+fn angle_between_lines(first: &Vector3<f64>, second: &Vector3<f64>) -> f64 {
+    (first.normalize().dot(&second.normalize()))
+        .abs()
+        .clamp(0.0, 1.0)
+        .acos()
+        .to_degrees()
+}
+
+fn rms(values: &[f64]) -> f64 {
+    (values.iter().map(|value| value.powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_cone_new() {
+        let cone = AlphaCone::new(90.0, -60.0, 70.0);
+        assert_eq!(cone.axis.trend.round(), 90.0);
+        assert_eq!(cone.axis.plunge.round(), 60.0);
+        assert_eq!(cone.half_angle.round(), 20.0);
+
+        // Up-hole axes are reversed to point downward
+        let cone = AlphaCone::new(90.0, 30.0, 70.0);
+        assert_eq!(cone.axis.trend.round(), 270.0);
+        assert_eq!(cone.axis.plunge.round(), 30.0);
+    }
+
+    #[test]
+    fn alpha_cone_poles_lie_on_cone() {
+        let cone = AlphaCone::new(262.7, -55.3, 65.0);
+        let poles = cone.poles(36);
+
+        assert_eq!(poles.len(), 36);
+        for pole in poles {
+            assert!(cone.misfit(&pole) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn alpha_cone_contains_oriented_pole() {
+        // The pole found from the same measurement with beta must lie on the cone
+        let cone = AlphaCone::new(262.7, -55.3, 65.0);
+        let plane =
+            crate::Plane::alpha_beta(262.7, -55.3, 65.0, 230.0, crate::BHOrientationLine::Top);
+        assert!(cone.misfit(&plane.pole) < 1e-6);
+    }
+
+    /// Build the cone each hole would see for a known plane
+    fn cone_for_plane(bearing: f64, inclination: f64, plane: &Plane) -> AlphaCone {
+        let axis = hole_axis(bearing.to_radians(), inclination.to_radians());
+        let pole = vector_from_trend_and_plunge(
+            plane.pole.trend.to_radians(),
+            plane.pole.plunge.to_radians(),
+        );
+        let alpha = pole.dot(&axis).abs().asin().to_degrees();
+        AlphaCone::new(bearing, inclination, alpha)
+    }
+
+    #[test]
+    fn solve_alpha_cones_three_holes() {
+        let plane = Plane::new(40.0, 65.0, None, None, None);
+        let cones = [
+            cone_for_plane(0.0, -60.0, &plane),
+            cone_for_plane(120.0, -50.0, &plane),
+            cone_for_plane(250.0, -75.0, &plane),
+        ];
+
+        let solutions = solve_alpha_cones(&cones).unwrap();
+        let best = &solutions[0];
+        assert!(best.misfit < 1e-4);
+        assert_eq!(best.plane.strike.round(), 40.0);
+        assert_eq!(best.plane.dip.round(), 65.0);
+        assert!(solutions[1].misfit > 1.0);
+    }
+
+    #[test]
+    fn solve_alpha_cones_two_holes_is_ambiguous() {
+        let plane = Plane::new(40.0, 65.0, None, None, None);
+        let cones = [
+            cone_for_plane(0.0, -60.0, &plane),
+            cone_for_plane(120.0, -50.0, &plane),
+        ];
+
+        let solutions = solve_alpha_cones(&cones).unwrap();
+        let exact = solutions
+            .iter()
+            .filter(|solution| solution.misfit < 1e-4)
+            .collect::<Vec<&ConeSolution>>();
+        assert!(exact.len() >= 2);
+        assert!(exact.iter().any(|solution| {
+            solution.plane.strike.round() == 40.0 && solution.plane.dip.round() == 65.0
+        }));
+    }
+
+    #[test]
+    fn solve_alpha_cones_rejects_parallel_holes() {
+        let cones = [
+            AlphaCone::new(10.0, -60.0, 40.0),
+            AlphaCone::new(11.0, -60.0, 50.0),
+        ];
+        assert!(solve_alpha_cones(&cones).is_err());
+        assert!(solve_alpha_cones(&cones[..1]).is_err());
+    }
+}
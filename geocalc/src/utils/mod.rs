@@ -1,5 +1,7 @@
 use crate::validation::error_if_out_of_range;
 
+pub mod vector;
+
 /// Get the dip direction from the strike using decimal degrees.
 pub fn dip_direction_from_strike(strike: &f64) -> f64 {
     error_if_out_of_range(strike, 0.0, 360.0).unwrap();
@@ -29,6 +31,11 @@ pub fn clockwise_from_input(input: &f64, add: f64, min: f64, max: f64) -> f64 {
     }
 }
 
+/// Wrap an azimuth in decimal degrees into the range 0.0 to 360.0.
+pub fn normalise_azimuth(azimuth: f64) -> f64 {
+    azimuth.rem_euclid(360.0)
+}
+
 /// Get the plunge from the dip using decimal degrees.
 pub fn dip_from_plunge(plunge: &f64) -> f64 {
     get_perpendicular_angle(plunge)
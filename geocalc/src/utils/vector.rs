@@ -0,0 +1,65 @@
+use na::Vector3;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+// Vectors use a right handed global coordinate system with x pointing east, y pointing north and z pointing up.
+// All angles are in radians.
+
+/// Returns the (trend, plunge) of the line parallel to `vector`.
+/// The line is always taken to point downward so that it plots in the lower hemisphere.
+pub fn trend_and_plunge_from_vector(vector: &Vector3<f64>) -> (f64, f64) {
+    let mut vector = vector.normalize();
+    if vector.z > 0.0 {
+        vector = -vector;
+    }
+
+    let horizontal = (vector.x.powi(2) + vector.y.powi(2)).sqrt();
+    // A vertical line has no trend, use north by convention
+    if horizontal < 1e-10 {
+        return (0.0, FRAC_PI_2);
+    }
+    let apparent_trend = (vector.x / horizontal).clamp(-1.0, 1.0).acos();
+
+    let mut trend = if vector.y <= 0.0 {
+        FRAC_PI_2 + apparent_trend
+    } else {
+        FRAC_PI_2 - apparent_trend
+    };
+
+    if trend < 0.0 {
+        trend += PI * 2.0;
+    }
+
+    (trend, -vector.z.clamp(-1.0, 1.0).asin())
+}
+
+/// Returns the downward pointing unit vector of a line with the given trend and plunge.
+pub fn vector_from_trend_and_plunge(trend: f64, plunge: f64) -> Vector3<f64> {
+    Vector3::new(
+        plunge.cos() * trend.sin(),
+        plunge.cos() * trend.cos(),
+        -plunge.sin(),
+    )
+}
+
+/// Returns the unit vector pointing along the borehole trajectory.
+/// The inclination is negative for holes pointing downward.
+pub fn hole_axis(bearing: f64, inclination: f64) -> Vector3<f64> {
+    Vector3::new(
+        inclination.cos() * bearing.sin(),
+        inclination.cos() * bearing.cos(),
+        inclination.sin(),
+    )
+}
+
+/// Returns two unit vectors that are perpendicular to `axis` and to each other.
+pub fn perpendicular_basis(axis: &Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let axis = axis.normalize();
+    let helper = if axis.z.abs() < 0.9 {
+        Vector3::z()
+    } else {
+        Vector3::x()
+    };
+    let u = axis.cross(&helper).normalize();
+    let v = axis.cross(&u);
+    (u, v)
+}